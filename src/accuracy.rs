@@ -0,0 +1,157 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use common::local::status::BeatState;
+
+use crate::widget::plot::TimeSeries;
+
+/// Number of beat intervals kept for the histogram and statistics.
+const ERROR_HISTORY: usize = 1024;
+/// Number of drift points kept for the drift plot before older ones are merged.
+const DRIFT_HISTORY: usize = 2048;
+/// How long an alert stays active after the last beat over the threshold.
+const ALERT_HOLD: Duration = Duration::from_secs(10);
+
+/// Measures how far received beat changes deviate from the ideal beat grid. Arrival times are
+/// taken when the packet is received, before it waits in the message queue.
+///
+/// Each interval between two consecutive beats is compared with the length of the first beat
+/// at the current playrate. The interval errors make up the jitter histogram, and their running
+/// sum is the drift from the grid since the transport started or last jumped.
+pub struct BeatAccuracy {
+    last: Option<(Instant, BeatState, u16)>,
+    /// Arrival of the first beat of the current run, and ideal time elapsed since then in µs.
+    anchor: Option<Instant>,
+    ideal_elapsed: f64,
+    /// Interval errors in ms, positive when a beat arrived late.
+    pub errors: VecDeque<f32>,
+    /// Drift from the grid in ms, against the arrival time of the beat.
+    pub drift: TimeSeries,
+    pub last_alert: Option<(Instant, f32)>,
+    pub alert_count: usize,
+}
+
+impl Default for BeatAccuracy {
+    fn default() -> Self {
+        Self {
+            last: None,
+            anchor: None,
+            ideal_elapsed: 0.0,
+            errors: VecDeque::with_capacity(ERROR_HISTORY),
+            drift: TimeSeries::default(),
+            last_alert: None,
+            alert_count: 0,
+        }
+    }
+}
+
+/// Summary of the interval errors in ms.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JitterStats {
+    pub mean: f32,
+    pub std_dev: f32,
+    pub max: f32,
+    /// Spread expected from the core only noticing a beat on its next main loop iteration.
+    pub core_std_dev: f32,
+    /// The remaining spread, from the network and the monitor.
+    pub network_std_dev: f32,
+}
+
+impl BeatAccuracy {
+    /// Feed a BeatData with the time its packet was received.
+    pub fn feed(
+        &mut self,
+        beat: &BeatState,
+        received: Instant,
+        running: bool,
+        playrate_percent: u16,
+        threshold_ms: f32,
+    ) {
+        if !running {
+            self.last = None;
+            self.anchor = None;
+            return;
+        }
+        let Some((last_time, last_beat, last_playrate)) = self.last else {
+            self.start_run(beat, received, playrate_percent);
+            return;
+        };
+        if beat.beat_idx == last_beat.beat_idx {
+            return;
+        }
+        // Only consecutive beats at a steady playrate are on the same grid
+        if beat.beat_idx != last_beat.next_beat_idx || playrate_percent != last_playrate {
+            self.start_run(beat, received, playrate_percent);
+            return;
+        }
+
+        let ideal = last_beat.beat.length as f64 * 100.0 / playrate_percent.max(1) as f64;
+        let actual = received.saturating_duration_since(last_time).as_secs_f64() * 1e6;
+        let error = ((actual - ideal) / 1000.0) as f32;
+        if self.errors.len() >= ERROR_HISTORY {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(error);
+        if error.abs() > threshold_ms {
+            // Alerts close together are shown as one, with the worst error among them
+            let worst = self
+                .active_alert()
+                .filter(|worst| worst.abs() > error.abs())
+                .unwrap_or(error);
+            self.last_alert = Some((received, worst));
+            self.alert_count += 1;
+        }
+
+        self.ideal_elapsed += ideal;
+        if let Some(anchor) = self.anchor {
+            let elapsed = received.saturating_duration_since(anchor).as_secs_f64() * 1e6;
+            // Place the beat on the wall clock by when it was received, not when it was handled
+            let time =
+                chrono::Utc::now().timestamp_millis() - received.elapsed().as_millis() as i64;
+            self.drift
+                .push(time, ((elapsed - self.ideal_elapsed) / 1000.0) as f32);
+            self.drift.trim(i64::MIN, DRIFT_HISTORY);
+        }
+        self.last = Some((received, *beat, playrate_percent));
+    }
+
+    fn start_run(&mut self, beat: &BeatState, received: Instant, playrate_percent: u16) {
+        self.last = Some((received, *beat, playrate_percent));
+        self.anchor = Some(received);
+        self.ideal_elapsed = 0.0;
+    }
+
+    /// The worst error of the latest alerts, held for a few seconds after the last one.
+    pub fn active_alert(&self) -> Option<f32> {
+        self.last_alert
+            .filter(|(time, _)| time.elapsed() < ALERT_HOLD)
+            .map(|(_, error)| error)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Statistics over the kept intervals. The core handles beats in its main loop, so a beat is
+    /// noticed up to one loop period late. Two such uniform delays make up each interval, which
+    /// gives the core's share of the spread; the rest is put down to the network.
+    pub fn stats(&self, process_freq_main: u32) -> Option<JitterStats> {
+        if self.errors.is_empty() {
+            return None;
+        }
+        let n = self.errors.len() as f32;
+        let mean = self.errors.iter().sum::<f32>() / n;
+        let variance = self.errors.iter().map(|e| (e - mean).powi(2)).sum::<f32>() / n;
+        let period_ms = 1000.0 / process_freq_main.max(1) as f32;
+        let core_variance = period_ms.powi(2) / 6.0;
+        Some(JitterStats {
+            mean,
+            std_dev: variance.sqrt(),
+            max: self.errors.iter().fold(0.0, |max, e| max.max(e.abs())),
+            core_std_dev: core_variance.sqrt(),
+            network_std_dev: (variance - core_variance).max(0.0).sqrt(),
+        })
+    }
+}
@@ -1,7 +1,7 @@
 use common::{
     local::{
         config::{LogItem, SystemConfiguration},
-        status::{AudioSourceState, CombinedStatus},
+        status::{AudioSourceState, ClipMetadata, CombinedStatus},
     },
     mem::network::ConnectionInfo,
     protocol::{
@@ -10,18 +10,30 @@ use common::{
     },
 };
 use crossbeam_channel::{unbounded, Receiver};
+use std::{collections::HashMap, time::Instant};
 
 use crate::{
+    accuracy::BeatAccuracy,
+    click::{ClickSettings, ClickState},
+    history::ChangeHistory,
+    rehearsal::RehearsalState,
     theme::{self, Theme},
     udp::UdpClient,
-    widget::textentry::TextEntry,
+    watchdog::{WatchdogSettings, WatchdogState},
+    widget::{meter::MeterState, metronome::MetronomeState, textentry::TextEntry},
     window::{
-        logs::LogWindowMemory, performance::PerformanceWindowMemory,
-        playback::PlaybackWindowMemory, security::SecurityWindowMemory, WindowTab,
+        jack::JackWindowMemory, locate::BarLocator, logs::LogWindowMemory,
+        performance::PerformanceWindowMemory, playback::PlaybackWindowMemory,
+        report::ReportWindowMemory, security::SecurityWindowMemory,
+        settings_audio::AudioProcessorState, show::ShowWindowMemory, sources::ChannelEditor,
+        stage::{StageState, StageWindowMemory}, time::TimeWindowMemory, WindowTab,
     },
 };
 use egui::FontFamily;
 
+/// Messages queued beyond this are too old to be worth showing, and are dropped unread.
+const BACKLOG_LIMIT: usize = 64;
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct ClicksMonitorApp {
@@ -32,17 +44,43 @@ pub struct ClicksMonitorApp {
     #[serde(skip)]
     pub udp_client: UdpClient,
     #[serde(skip)]
-    pub rx: Receiver<(Message, usize)>,
+    pub rx: Receiver<(Message, usize, Instant)>,
     #[serde(skip)]
     pub ctx: egui::Context,
     #[serde(skip)]
     pub sources_gains: Vec<f32>,
     #[serde(skip)]
+    pub input_meters: Vec<MeterState>,
+    #[serde(skip)]
+    pub output_meters: Vec<MeterState>,
+    #[serde(skip)]
     pub text_entry: TextEntry,
     #[serde(skip)]
     pub system_config: SystemConfiguration,
     #[serde(skip)]
     pub log_entries: Vec<LogItem>,
+    #[serde(skip)]
+    pub clip_info: HashMap<(u8, u16), ClipMetadata>,
+    #[serde(skip)]
+    pub channel_editor: ChannelEditor,
+    #[serde(skip)]
+    pub audio_processor: AudioProcessorState,
+    #[serde(skip)]
+    pub started: Instant,
+    #[serde(skip)]
+    pub bar_locator: BarLocator,
+    #[serde(skip)]
+    pub rehearsal: RehearsalState,
+    #[serde(skip)]
+    pub stage: StageState,
+    #[serde(skip)]
+    pub metronome: MetronomeState,
+    #[serde(skip)]
+    pub click: ClickState,
+    #[serde(skip)]
+    pub beat_accuracy: BeatAccuracy,
+    #[serde(skip)]
+    pub watchdog: WatchdogState,
     pub host_connection_info: ConnectionInfo,
     pub local_memory: LocalMemory,
     pub theme: Theme,
@@ -55,6 +93,16 @@ pub struct LocalMemory {
     pub log: LogWindowMemory,
     pub performance: PerformanceWindowMemory,
     pub security: SecurityWindowMemory,
+    pub jack: JackWindowMemory,
+    pub history: ChangeHistory,
+    pub time: TimeWindowMemory,
+    pub report: ReportWindowMemory,
+    pub show: ShowWindowMemory,
+    pub stage: StageWindowMemory,
+    pub click: ClickSettings,
+    pub watchdog: WatchdogSettings,
+    pub loop_count_in_bars: u8,
+    pub count_in_bars: u8,
 }
 
 impl Default for ClicksMonitorApp {
@@ -62,6 +110,8 @@ impl Default for ClicksMonitorApp {
         Self {
             system_config: SystemConfiguration::default(),
             sources_gains: vec![0.0f32; 32],
+            input_meters: vec![MeterState::default(); 32],
+            output_meters: vec![MeterState::default(); 32],
             ctx: egui::Context::default(),
             status: CombinedStatus::default(),
             udp_client: UdpClient::new(),
@@ -72,6 +122,17 @@ impl Default for ClicksMonitorApp {
             text_entry: TextEntry::new(),
             last_heartbeat: Heartbeat::default(),
             log_entries: vec![],
+            clip_info: HashMap::new(),
+            channel_editor: ChannelEditor::default(),
+            audio_processor: AudioProcessorState::default(),
+            started: Instant::now(),
+            bar_locator: BarLocator::default(),
+            rehearsal: RehearsalState::default(),
+            stage: StageState::default(),
+            metronome: MetronomeState::default(),
+            click: ClickState::default(),
+            beat_accuracy: BeatAccuracy::default(),
+            watchdog: WatchdogState::default(),
         }
     }
 }
@@ -108,7 +169,7 @@ impl ClicksMonitorApp {
         self.theme = theme;
     }
 
-    pub fn handle_cc_message(&mut self, msg: Message, size: usize) {
+    pub fn handle_cc_message(&mut self, msg: Message, size: usize, received: Instant) {
         self.udp_client.active = true;
         let tally_pre = self
             .udp_client
@@ -119,6 +180,7 @@ impl ClicksMonitorApp {
             .rx_message_tally
             .insert(msg.to_type(), (tally_pre.0 + 1, tally_pre.1 + size));
         //println!("Received Message {:?}", msg.clone());
+        crate::window::report::record(self, &msg);
         match msg {
             Message::Small(SmallMessage::TransportData(status)) => {
                 self.status.transport = status;
@@ -131,10 +193,30 @@ impl ClicksMonitorApp {
                 self.status.sources[1] = AudioSourceState::TimeStatus(status);
             }
             Message::Small(SmallMessage::BeatData(beat)) => {
+                crate::watchdog::on_beat_data(self);
+                crate::rehearsal::on_beat(self, &beat);
+                self.metronome.feed(
+                    &beat,
+                    &self.status.cue.cue,
+                    self.status.transport.running,
+                    received,
+                );
+                crate::click::on_beat(self, &beat, received);
+                self.beat_accuracy.feed(
+                    &beat,
+                    received,
+                    self.status.transport.running,
+                    self.status.transport.playrate_percent,
+                    self.local_memory.performance.beat_alert_ms,
+                );
                 self.status.sources[0] = AudioSourceState::BeatStatus(beat);
             }
             Message::Large(LargeMessage::CueData(cue)) => {
+                if cue.cue_idx != self.status.cue.cue_idx {
+                    crate::rehearsal::on_cue_changed(self);
+                }
                 self.status.cue = cue;
+                crate::window::locate::on_cue_data(self);
             }
             Message::Large(LargeMessage::ShowData(show)) => {
                 self.status.show = show;
@@ -146,6 +228,7 @@ impl ClicksMonitorApp {
                 self.status.network_status = status;
             }
             Message::Large(LargeMessage::JACKStateChanged(status)) => {
+                self.audio_processor.on_jack_status(&status);
                 self.status.jack_status = status;
             }
             Message::Small(SmallMessage::ShutdownOccured) => {
@@ -155,18 +238,26 @@ impl ClicksMonitorApp {
                 for i in 0..self.sources_gains.len() {
                     self.sources_gains[i] = config.channels[i].gain;
                 }
+                self.channel_editor
+                    .on_configuration_changed(&self.system_config, &config);
                 self.system_config = config;
             }
             Message::Small(SmallMessage::Heartbeat(heartbeat)) => {
                 self.last_heartbeat = heartbeat;
-                self.local_memory
-                    .performance
-                    .heartbeats
-                    .push_back(heartbeat.clone());
-                while self.local_memory.performance.heartbeats.len() > 300 {
-                    self.local_memory.performance.heartbeats.pop_front();
+                crate::window::performance::record_heartbeat(self, &heartbeat);
+                crate::watchdog::on_heartbeat(self, &heartbeat);
+            }
+            Message::Small(SmallMessage::LevelData(levels)) => {
+                for (meter, level) in self.input_meters.iter_mut().zip(levels.inputs) {
+                    meter.feed(level);
+                }
+                for (meter, level) in self.output_meters.iter_mut().zip(levels.outputs) {
+                    meter.feed(level);
                 }
             }
+            Message::Large(LargeMessage::ClipData(info)) => {
+                self.clip_info.insert((info.channel, info.clip_idx), info);
+            }
             Message::Large(LargeMessage::Log(item)) => self.log_entries.push(item),
             _ => {}
         }
@@ -217,14 +308,40 @@ impl eframe::App for ClicksMonitorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.request_repaint();
 
+        let queue_depth = self.rx.len();
+        crate::window::performance::update(self, queue_depth);
+        crate::watchdog::update(self, queue_depth);
+        if queue_depth > BACKLOG_LIMIT {
+            let discarded = self.rx.try_iter().count();
+            self.watchdog.log_discarded(discarded, BACKLOG_LIMIT);
+        }
         loop {
             match self.rx.try_recv() {
-                Ok((msg, size)) => self.handle_cc_message(msg, size),
+                Ok((msg, size, received)) => self.handle_cc_message(msg, size, received),
                 Err(crossbeam_channel::TryRecvError::Empty) => break,
                 Err(err) => println!("rx error: {}", err),
             }
         }
 
+        crate::history::handle_shortcuts(self, ctx);
+        crate::window::locate::handle_shortcuts(self, ctx);
+        crate::window::playback::update(self);
+        crate::window::settings_audio::update(self);
+        crate::rehearsal::update(self);
+        crate::click::update(self);
+        crate::window::stage::handle_shortcuts(self, ctx);
+        crate::watchdog::popup(self, ctx);
+
+        if self.stage.active {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::NONE)
+                .show(ctx, |ui| crate::window::stage::display(self, ui));
+            // Shortcuts still work on the stage display, so keep their overlays visible
+            crate::window::playback::countdown_overlay(self, ctx);
+            self.text_entry = self.text_entry.clone().display(self).clone();
+            return;
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             crate::window::statusbar::display(self, ui);
         });
@@ -239,6 +356,9 @@ impl eframe::App for ClicksMonitorApp {
             WindowTab::SourcesOverview => {
                 crate::window::sources::display(self, ui);
             }
+            WindowTab::CueShow => {
+                crate::window::show::display(self, ui);
+            }
             WindowTab::CueTimeline => {
                 crate::window::cue::display(self, ui);
             }
@@ -257,21 +377,41 @@ impl eframe::App for ClicksMonitorApp {
             WindowTab::CueEvents => {
                 crate::window::events::display(self, ui);
             }
+            WindowTab::CueTempo => {
+                crate::window::tempo::display(self, ui);
+            }
+            WindowTab::ControlReport => {
+                crate::window::report::display(self, ui);
+            }
             WindowTab::SystemLogs => {
                 crate::window::logs::display(self, ui);
             }
             WindowTab::SystemPerformance => {
                 crate::window::performance::display(self, ui);
             }
+            WindowTab::SystemAlarms => {
+                crate::window::alarms::display(self, ui);
+            }
+            WindowTab::SystemChannels => {
+                crate::window::sources::configuration_window(self, ui);
+            }
+            WindowTab::SystemRouting => {
+                crate::window::jack::display(self, ui);
+            }
             WindowTab::SystemAudio => {
                 crate::window::settings_audio::display(self, ui);
             }
             WindowTab::SystemNetwork => {
                 crate::window::network::display(self, ui);
             }
+            WindowTab::PreferencesClick => {
+                crate::window::click::display(self, ui);
+            }
             _ => {}
         });
 
+        crate::window::playback::countdown_overlay(self, ctx);
+
         self.text_entry = self.text_entry.clone().display(self).clone();
     }
 }
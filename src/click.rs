@@ -0,0 +1,537 @@
+use std::{
+    f32::consts::TAU,
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use common::local::status::BeatState;
+use cpal::{
+    FromSample, SizedSample,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+
+use crate::{app::ClicksMonitorApp, rehearsal::CountIn};
+
+const CLICK_LENGTH: Duration = Duration::from_millis(40);
+const CLICK_FREQ: f32 = 1000.0;
+const ACCENT_FREQ: f32 = 1500.0;
+/// Accented clicks are this much louder than the others.
+const ACCENT_GAIN: f32 = 1.6;
+/// Sample rate and block length of the file and null backends.
+const VIRTUAL_SAMPLE_RATE: u32 = 48000;
+const VIRTUAL_BLOCK: Duration = Duration::from_millis(10);
+/// Number of taps averaged for latency calibration.
+const CALIBRATION_TAPS: usize = 8;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum ClickBackend {
+    /// The default output of the local sound card.
+    SoundCard,
+    /// Render in real time to a WAV file, for testing without hardware.
+    File,
+    /// Render in real time and throw the audio away.
+    Null,
+}
+
+impl ClickBackend {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::SoundCard => "Sound card",
+            Self::File => "WAV file",
+            Self::Null => "Null",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ClickSettings {
+    pub enabled: bool,
+    pub backend: ClickBackend,
+    pub file_path: String,
+    pub volume_db: f32,
+    pub accent: bool,
+    /// How much earlier than the predicted beat the click is played, to make up for output
+    /// latency. Negative values delay the click.
+    pub latency_offset_ms: f32,
+}
+
+impl Default for ClickSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: ClickBackend::SoundCard,
+            file_path: "click.wav".to_string(),
+            volume_db: -12.0,
+            accent: true,
+            latency_offset_ms: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ClickEvent {
+    pub beat_idx: u16,
+    pub at: Instant,
+    pub accent: bool,
+}
+
+enum ClickCommand {
+    /// Play a click, replacing any click not yet started for the same beat.
+    Schedule(ClickEvent),
+    /// Drop all clicks not yet started.
+    Clear,
+    SetGain(f32),
+}
+
+struct Voice {
+    /// Frames left before the click starts.
+    delay: usize,
+    pos: usize,
+    accent: bool,
+}
+
+/// Renders scheduled clicks into interleaved sample buffers. Shared by all backends.
+struct ClickSynth {
+    rx: Receiver<ClickCommand>,
+    sample_rate: f32,
+    gain: f32,
+    pending: Vec<ClickEvent>,
+    voices: Vec<Voice>,
+    last_started: Option<ClickEvent>,
+    played: Arc<AtomicU32>,
+}
+
+impl ClickSynth {
+    fn handle_commands(&mut self) {
+        for command in self.rx.try_iter() {
+            match command {
+                ClickCommand::Schedule(event) => {
+                    // A later BeatData for a click that has already started
+                    if self.last_started.is_some_and(|last| {
+                        last.beat_idx == event.beat_idx
+                            && (last.at.max(event.at) - last.at.min(event.at)) < CLICK_LENGTH * 5
+                    }) {
+                        continue;
+                    }
+                    self.pending.retain(|e| e.beat_idx != event.beat_idx);
+                    self.pending.push(event);
+                }
+                ClickCommand::Clear => self.pending.clear(),
+                ClickCommand::SetGain(gain) => self.gain = gain,
+            }
+        }
+    }
+
+    /// Fill a buffer whose first frame will be heard at `start`.
+    fn render(&mut self, out: &mut [f32], channels: usize, start: Instant) {
+        self.handle_commands();
+        let frames = out.len() / channels.max(1);
+        let end = start + Duration::from_secs_f32(frames as f32 / self.sample_rate);
+
+        let (due, later): (Vec<ClickEvent>, Vec<ClickEvent>) =
+            self.pending.iter().partition(|e| e.at < end);
+        self.pending = later;
+        for event in due {
+            // Too late to be of any use
+            if start.saturating_duration_since(event.at) > CLICK_LENGTH {
+                continue;
+            }
+            self.voices.push(Voice {
+                delay: (event.at.saturating_duration_since(start).as_secs_f32() * self.sample_rate)
+                    as usize,
+                pos: 0,
+                accent: event.accent,
+            });
+            self.last_started = Some(event);
+            self.played.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let length = (CLICK_LENGTH.as_secs_f32() * self.sample_rate) as usize;
+        for frame in out.chunks_mut(channels.max(1)) {
+            let mut sample = 0.0;
+            for voice in &mut self.voices {
+                if voice.delay > 0 {
+                    voice.delay -= 1;
+                    continue;
+                }
+                if voice.pos >= length {
+                    continue;
+                }
+                let t = voice.pos as f32 / self.sample_rate;
+                let (freq, amp) = if voice.accent {
+                    (ACCENT_FREQ, ACCENT_GAIN)
+                } else {
+                    (CLICK_FREQ, 1.0)
+                };
+                sample += (TAU * freq * t).sin() * amp * (1.0 - voice.pos as f32 / length as f32);
+                voice.pos += 1;
+            }
+            frame.fill((sample * self.gain).clamp(-1.0, 1.0));
+        }
+        self.voices.retain(|v| v.pos < length);
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// A running click output. Dropping it stops the audio.
+pub struct ClickOutput {
+    tx: Sender<ClickCommand>,
+    played: Arc<AtomicU32>,
+    pub description: String,
+    _stream: Option<cpal::Stream>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ClickOutput {
+    pub fn start(settings: &ClickSettings) -> Result<Self, String> {
+        let (tx, rx) = unbounded();
+        let played = Arc::new(AtomicU32::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let synth = |sample_rate: f32| ClickSynth {
+            rx: rx.clone(),
+            sample_rate,
+            gain: db_to_gain(settings.volume_db),
+            pending: vec![],
+            voices: vec![],
+            last_started: None,
+            played: played.clone(),
+        };
+
+        let mut output = Self {
+            tx: tx.clone(),
+            played: played.clone(),
+            description: String::new(),
+            _stream: None,
+            stop: stop.clone(),
+            worker: None,
+        };
+        match settings.backend {
+            ClickBackend::SoundCard => {
+                let device = cpal::default_host()
+                    .default_output_device()
+                    .ok_or("No output device found")?;
+                let config = device.default_output_config().map_err(|e| e.to_string())?;
+                let synth = synth(config.sample_rate().0 as f32);
+                let stream = match config.sample_format() {
+                    cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, synth),
+                    cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, synth),
+                    cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, synth),
+                    format => return Err(format!("Unsupported sample format {}", format)),
+                }?;
+                stream.play().map_err(|e| e.to_string())?;
+                output.description = format!(
+                    "{}, {} Hz",
+                    device.name().unwrap_or("Unknown device".to_string()),
+                    config.sample_rate().0
+                );
+                output._stream = Some(stream);
+            }
+            ClickBackend::File | ClickBackend::Null => {
+                let file = match settings.backend {
+                    ClickBackend::File => Some(
+                        WavWriter::create(&settings.file_path, VIRTUAL_SAMPLE_RATE)
+                            .map_err(|e| format!("{}: {}", settings.file_path, e))?,
+                    ),
+                    _ => None,
+                };
+                output.description = match file {
+                    Some(_) => format!("Writing {}", settings.file_path),
+                    None => "Discarding audio".to_string(),
+                };
+                let synth = synth(VIRTUAL_SAMPLE_RATE as f32);
+                output.worker = Some(std::thread::spawn(move || {
+                    virtual_output(synth, file, stop)
+                }));
+            }
+        }
+        Ok(output)
+    }
+
+    pub fn schedule(&self, event: ClickEvent) {
+        let _ = self.tx.send(ClickCommand::Schedule(event));
+    }
+
+    pub fn clear(&self) {
+        let _ = self.tx.send(ClickCommand::Clear);
+    }
+
+    pub fn set_volume(&self, volume_db: f32) {
+        let _ = self.tx.send(ClickCommand::SetGain(db_to_gain(volume_db)));
+    }
+
+    pub fn played(&self) -> u32 {
+        self.played.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ClickOutput {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    mut synth: ClickSynth,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels() as usize;
+    let mut buffer = vec![];
+    device
+        .build_output_stream(
+            &config.config(),
+            move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+                let timestamp = info.timestamp();
+                let output_latency = timestamp
+                    .playback
+                    .duration_since(&timestamp.callback)
+                    .unwrap_or_default();
+                buffer.resize(data.len(), 0.0);
+                synth.render(&mut buffer, channels, Instant::now() + output_latency);
+                for (out, sample) in data.iter_mut().zip(&buffer) {
+                    *out = T::from_sample(*sample);
+                }
+            },
+            |err| println!("click output error: {}", err),
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Render in real time blocks on a thread, writing to a file if given.
+fn virtual_output(mut synth: ClickSynth, mut file: Option<WavWriter>, stop: Arc<AtomicBool>) {
+    let frames = (VIRTUAL_SAMPLE_RATE as f32 * VIRTUAL_BLOCK.as_secs_f32()) as usize;
+    let mut buffer = vec![0.0; frames];
+    let mut block_start = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(block_start.saturating_duration_since(Instant::now()));
+        synth.render(&mut buffer, 1, block_start);
+        if let Some(file) = &mut file
+            && let Err(err) = file.write(&buffer)
+        {
+            println!("click file error: {}", err);
+            return;
+        }
+        block_start += VIRTUAL_BLOCK;
+    }
+    if let Some(file) = file
+        && let Err(err) = file.finish()
+    {
+        println!("click file error: {}", err);
+    }
+}
+
+/// Minimal mono 16 bit WAV writer. The header sizes are filled in by `finish`.
+struct WavWriter {
+    writer: BufWriter<File>,
+    data_bytes: u32,
+}
+
+impl WavWriter {
+    fn create(path: &str, sample_rate: u32) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(b"RIFF\0\0\0\0WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&1u16.to_le_bytes())?; // Mono
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(b"data\0\0\0\0")?;
+        Ok(Self {
+            writer,
+            data_bytes: 0,
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        for sample in samples {
+            self.writer
+                .write_all(&((sample * i16::MAX as f32) as i16).to_le_bytes())?;
+        }
+        self.data_bytes += samples.len() as u32 * 2;
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(36 + self.data_bytes).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&self.data_bytes.to_le_bytes())?;
+        self.writer.flush()
+    }
+}
+
+#[derive(Default)]
+pub struct ClickState {
+    pub output: Option<ClickOutput>,
+    pub error: Option<String>,
+    /// Settings the output was started with, to restart it when they change.
+    started_with: Option<ClickSettings>,
+    /// Times clicks were scheduled for, to compare calibration taps against.
+    scheduled: Vec<Instant>,
+    /// Tap offsets from the nearest click in ms, positive when tapping late.
+    pub taps: Vec<f32>,
+}
+
+/// Start, stop and restart the output to match the settings. Called every frame.
+pub fn update(app: &mut ClicksMonitorApp) {
+    let settings = &app.local_memory.click;
+    let restart = app.click.started_with.as_ref().is_some_and(|started| {
+        started.backend != settings.backend || started.file_path != settings.file_path
+    });
+    if !settings.enabled || restart {
+        app.click.output = None;
+        app.click.started_with = None;
+    }
+    if !settings.enabled {
+        app.click.error = None;
+    } else if app.click.output.is_none() && app.click.error.is_none() {
+        match ClickOutput::start(settings) {
+            Ok(output) => app.click.output = Some(output),
+            Err(err) => app.click.error = Some(err),
+        }
+        app.click.started_with = Some(settings.clone());
+    }
+}
+
+/// When to play the click for a beat starting at `beat_start`, after the latency offset.
+fn click_time(settings: &ClickSettings, beat_start: Instant) -> Instant {
+    let now = Instant::now();
+    let offset = Duration::from_secs_f32(settings.latency_offset_ms.abs() / 1000.0);
+    if settings.latency_offset_ms >= 0.0 {
+        beat_start.checked_sub(offset).unwrap_or(now).max(now)
+    } else {
+        beat_start + offset
+    }
+}
+
+/// Schedule a click for the start of the next beat. Called for every BeatData, with the time
+/// the packet was received so the time spent queued is not added to the click.
+pub fn on_beat(app: &mut ClicksMonitorApp, beat: &BeatState, received: Instant) {
+    let Some(output) = &app.click.output else {
+        return;
+    };
+    if !app.status.transport.running {
+        // The count-in clicks are scheduled while the transport is stopped
+        if app.rehearsal.count_in.is_none() {
+            output.clear();
+        }
+        return;
+    }
+    let settings = &app.local_memory.click;
+    let at = click_time(
+        settings,
+        received + Duration::from_micros(beat.us_to_next_beat as u64),
+    );
+    let accent = settings.accent
+        && app
+            .status
+            .cue
+            .cue
+            .beats
+            .get(beat.next_beat_idx as usize)
+            .is_some_and(|b| b.count == 1);
+    output.schedule(ClickEvent {
+        beat_idx: beat.next_beat_idx,
+        at,
+        accent,
+    });
+    if app
+        .click
+        .scheduled
+        .last()
+        .is_none_or(|last| at.saturating_duration_since(*last) > CLICK_LENGTH * 5)
+    {
+        app.click.scheduled.push(at);
+        if app.click.scheduled.len() > CALIBRATION_TAPS * 2 {
+            app.click.scheduled.remove(0);
+        }
+    }
+}
+
+/// Schedule every click of a count-in and of the target beat it leads into. The transport is
+/// stopped while counting in, so no beat data arrives to schedule them from.
+pub fn on_count_in(app: &mut ClicksMonitorApp, count_in: &CountIn, beats_per_bar: u8) {
+    let Some(output) = &app.click.output else {
+        return;
+    };
+    output.clear();
+    let settings = &app.local_memory.click;
+    let target_downbeat = app
+        .status
+        .cue
+        .cue
+        .beats
+        .get(count_in.target as usize)
+        .is_some_and(|b| b.count == 1);
+    for i in 0..=count_in.beats {
+        let beat_start = count_in.started + count_in.beat_length * i as u32;
+        let (beat_idx, downbeat) = if i == count_in.beats {
+            (count_in.target, target_downbeat)
+        } else {
+            // Out of the way of real beat indices, so they are not replaced by beat data
+            (u16::MAX - i as u16, i % beats_per_bar.max(1) == 0)
+        };
+        output.schedule(ClickEvent {
+            beat_idx,
+            at: click_time(settings, beat_start),
+            accent: settings.accent && downbeat,
+        });
+    }
+}
+
+/// Drop count-in clicks that have not played yet.
+pub fn cancel_count_in(app: &mut ClicksMonitorApp) {
+    if let Some(output) = &app.click.output
+        && !app.status.transport.running
+    {
+        output.clear();
+    }
+}
+
+/// Record a tap against the nearest scheduled click.
+pub fn tap(app: &mut ClicksMonitorApp) {
+    let now = Instant::now();
+    let Some(nearest) = app.click.scheduled.iter().min_by_key(|at| {
+        now.saturating_duration_since(**at)
+            .max(at.saturating_duration_since(now))
+    }) else {
+        return;
+    };
+    let ms = if now >= *nearest {
+        now.duration_since(*nearest).as_secs_f32() * 1000.0
+    } else {
+        -nearest.duration_since(now).as_secs_f32() * 1000.0
+    };
+    app.click.taps.push(ms);
+    if app.click.taps.len() > CALIBRATION_TAPS {
+        app.click.taps.remove(0);
+    }
+}
+
+/// Mean tap offset in ms once enough taps have been made.
+pub fn calibration(app: &ClicksMonitorApp) -> Option<f32> {
+    (app.click.taps.len() >= CALIBRATION_TAPS / 2)
+        .then(|| app.click.taps.iter().sum::<f32>() / app.click.taps.len() as f32)
+}
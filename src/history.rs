@@ -0,0 +1,224 @@
+use common::protocol::request::{ControlAction, Request};
+use egui::{Key, KeyboardShortcut, Modifiers, RichText};
+
+use crate::app::ClicksMonitorApp;
+
+const UNDO_LIMIT: usize = 64;
+
+pub const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+
+/// A routing or gain change made from this monitor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MixerChange {
+    Routing { from: u8, to: u8, connect: bool },
+    Gain { channel: u8, from: f32, to: f32 },
+}
+
+impl MixerChange {
+    fn inverse(&self) -> Self {
+        match *self {
+            Self::Routing { from, to, connect } => Self::Routing {
+                from,
+                to,
+                connect: !connect,
+            },
+            Self::Gain { channel, from, to } => Self::Gain {
+                channel,
+                from: to,
+                to: from,
+            },
+        }
+    }
+
+    fn request(&self) -> Request {
+        match *self {
+            Self::Routing { from, to, connect } => Request::ChangeRouting(from, to, connect),
+            Self::Gain { channel, to, .. } => {
+                Request::ControlAction(ControlAction::SetChannelGain(channel, to))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match *self {
+            Self::Routing { from, to, connect } => format!(
+                "{} in {} {} out {}",
+                if connect { "Connect" } else { "Disconnect" },
+                from + 1,
+                if connect { "to" } else { "from" },
+                to + 1
+            ),
+            Self::Gain { channel, from, to } => {
+                format!("Ch {} gain {:+.1} -> {:+.1} dB", channel + 1, from, to)
+            }
+        }
+    }
+}
+
+/// Undo stack and staging area for mixer changes. Changes that belong together (a drag across
+/// the routing matrix, a preset) are grouped and undone as one step.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub struct ChangeHistory {
+    /// When armed, changes are staged locally and only sent once confirmed.
+    pub armed: bool,
+    #[serde(skip)]
+    undo: Vec<Vec<MixerChange>>,
+    #[serde(skip)]
+    staged: Vec<MixerChange>,
+    #[serde(skip)]
+    group: Option<Vec<MixerChange>>,
+}
+
+impl ChangeHistory {
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.group = Some(vec![]);
+    }
+
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take() {
+            self.push_undo(group);
+        }
+    }
+
+    fn push_undo(&mut self, group: Vec<MixerChange>) {
+        if group.is_empty() {
+            return;
+        }
+        self.undo.push(group);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// The staged value of a crosspoint, if one is waiting for confirmation.
+    pub fn staged_routing(&self, from: usize, to: usize) -> Option<bool> {
+        self.staged.iter().rev().find_map(|change| match *change {
+            MixerChange::Routing {
+                from: f,
+                to: t,
+                connect,
+            } if f as usize == from && t as usize == to => Some(connect),
+            _ => None,
+        })
+    }
+
+    pub fn staged_count(&self) -> usize {
+        self.staged.len()
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+}
+
+/// Send a change to the core, or stage it if the history is armed.
+pub fn submit(app: &mut ClicksMonitorApp, change: MixerChange) {
+    if app.local_memory.history.armed {
+        app.local_memory.history.staged.push(change);
+        return;
+    }
+    app.udp_client.send_msg(change.request());
+    let history = &mut app.local_memory.history;
+    match &mut history.group {
+        Some(group) => group.push(change),
+        None => history.push_undo(vec![change]),
+    }
+}
+
+/// Send all staged changes as one undo step.
+pub fn confirm_staged(app: &mut ClicksMonitorApp) {
+    let staged = std::mem::take(&mut app.local_memory.history.staged);
+    for change in &staged {
+        app.udp_client.send_msg(change.request());
+    }
+    app.local_memory.history.push_undo(staged);
+}
+
+pub fn discard_staged(app: &mut ClicksMonitorApp) {
+    let staged = std::mem::take(&mut app.local_memory.history.staged);
+    for change in staged.iter().rev() {
+        if let MixerChange::Gain { channel, from, .. } = *change {
+            app.sources_gains[channel as usize] = from;
+        }
+    }
+}
+
+/// Drop the latest staged change, or revert the latest sent change group. When armed, the
+/// reverting changes are staged like any other.
+pub fn undo(app: &mut ClicksMonitorApp) {
+    if let Some(change) = app.local_memory.history.staged.pop() {
+        if let MixerChange::Gain { channel, from, .. } = change {
+            app.sources_gains[channel as usize] = from;
+        }
+        return;
+    }
+    app.local_memory.history.end_group();
+    let Some(group) = app.local_memory.history.undo.pop() else {
+        return;
+    };
+    for change in group.iter().rev() {
+        let inverse = change.inverse();
+        if let MixerChange::Gain { channel, to, .. } = inverse {
+            app.sources_gains[channel as usize] = to;
+        }
+        if app.local_memory.history.armed {
+            app.local_memory.history.staged.push(inverse);
+        } else {
+            app.udp_client.send_msg(inverse.request());
+        }
+    }
+}
+
+/// Handle the undo shortcut, unless a text field has keyboard focus.
+pub fn handle_shortcuts(app: &mut ClicksMonitorApp, ctx: &egui::Context) {
+    if ctx.wants_keyboard_input() || !app.local_memory.security.allow_interaction {
+        return;
+    }
+    if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+        undo(app);
+    }
+}
+
+pub fn controls(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.vertical(|ui| {
+        if !app.local_memory.security.allow_interaction {
+            ui.disable();
+        }
+        ui.checkbox(
+            &mut app.local_memory.history.armed,
+            "Arm mode (stage routing and gain changes)",
+        );
+        ui.horizontal(|ui| {
+            let staged = app.local_memory.history.staged_count();
+            if ui
+                .add_enabled(
+                    staged > 0,
+                    egui::Button::new(format!("Apply {} staged", staged)).fill(app.theme.cued_prim),
+                )
+                .clicked()
+            {
+                confirm_staged(app);
+            }
+            if ui
+                .add_enabled(staged > 0, egui::Button::new("Discard"))
+                .clicked()
+            {
+                discard_staged(app);
+            }
+            let undo_text = format!("Undo ({})", ui.ctx().format_shortcut(&UNDO_SHORTCUT));
+            if ui
+                .add_enabled(
+                    staged > 0 || app.local_memory.history.undo_count() > 0,
+                    egui::Button::new(undo_text),
+                )
+                .clicked()
+            {
+                undo(app);
+            }
+        });
+        for change in &app.local_memory.history.staged {
+            ui.label(RichText::new(change.describe()).color(app.theme.warn_prim));
+        }
+    });
+}
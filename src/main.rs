@@ -1,9 +1,15 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+mod accuracy;
 mod app;
+mod click;
+mod history;
+mod rehearsal;
 mod theme;
+mod timing;
 mod udp;
+mod watchdog;
 mod widget;
 mod window;
 
@@ -0,0 +1,267 @@
+use std::time::{Duration, Instant};
+
+use common::{
+    cue::Cue,
+    local::status::BeatState,
+    protocol::request::{ControlAction, Request},
+};
+
+use crate::app::ClicksMonitorApp;
+
+/// How early the loop seek is sent before the end beat runs out, to cover network latency.
+const SEEK_LEAD: Duration = Duration::from_millis(5);
+pub const MSG_COUNT_IN_UNAVAILABLE: &str = "Start the local click to count in";
+
+/// A beat range the monitor keeps the transport looping, end beat included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoopRegion {
+    pub start: u16,
+    pub end: u16,
+    /// Number of times the transport has been sent back to the start.
+    pub count: u32,
+    /// Bars of count-in before each repeat, 0 to seek straight back.
+    pub count_in_bars: u8,
+}
+
+/// A count-in running on the monitor while the transport waits at the target beat.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CountIn {
+    pub target: u16,
+    pub beats: u8,
+    pub beat_length: Duration,
+    pub started: Instant,
+}
+
+impl CountIn {
+    /// Beats left to count, counting down to 1.
+    pub fn beats_left(&self) -> u8 {
+        let elapsed = self.started.elapsed().as_secs_f32() / self.beat_length.as_secs_f32();
+        self.beats.saturating_sub(elapsed as u8)
+    }
+
+    pub fn done(&self) -> bool {
+        self.started.elapsed() >= self.beat_length * self.beats as u32
+    }
+}
+
+#[derive(Default)]
+pub struct RehearsalState {
+    pub loop_region: Option<LoopRegion>,
+    pub count_in: Option<CountIn>,
+    /// Beat range selected in the cue timeline, and the beat the drag started at.
+    pub selection: Option<(u16, u16)>,
+    pub drag_anchor: Option<u16>,
+    /// When to send the transport back to the loop start.
+    loop_deadline: Option<Instant>,
+}
+
+/// Number of beats in the bar containing a beat.
+pub fn beats_in_bar(cue: &Cue, beat_idx: u16) -> u8 {
+    let Some(beat) = cue.beats.get(beat_idx as usize) else {
+        return 4;
+    };
+    cue.beats
+        .iter()
+        .skip(beat_idx as usize)
+        .take_while(|b| b.bar_number == beat.bar_number)
+        .map(|b| b.count)
+        .max()
+        .unwrap_or(beat.count)
+        .max(1)
+}
+
+/// The count-in is only heard through the local click, so it is offered only while the click
+/// output is running.
+pub fn count_in_available(app: &ClicksMonitorApp) -> bool {
+    app.click.output.is_some()
+}
+
+/// Stop, wait at the target beat and count in the given number of bars at the target's tempo,
+/// then start the transport. The count-in is heard through the local click.
+pub fn start_count_in(app: &mut ClicksMonitorApp, target: u16, bars: u8) {
+    let cue = &app.status.cue.cue;
+    let Some(beat) = cue.beats.get(target as usize) else {
+        return;
+    };
+    let beat_length = Duration::from_micros(
+        beat.length as u64 * 100 / app.status.transport.playrate_percent.max(1) as u64,
+    );
+    let beats_per_bar = beats_in_bar(cue, target);
+    let beats = beats_per_bar * bars;
+    app.udp_client
+        .send_msg(Request::ControlAction(ControlAction::TransportStop));
+    app.udp_client
+        .send_msg(Request::ControlAction(ControlAction::TransportJumpBeat(
+            target,
+        )));
+    let count_in = CountIn {
+        target,
+        beats,
+        beat_length,
+        started: Instant::now(),
+    };
+    app.rehearsal.count_in = Some(count_in);
+    crate::click::on_count_in(app, &count_in, beats_per_bar);
+}
+
+pub fn cancel_count_in(app: &mut ClicksMonitorApp) {
+    app.rehearsal.count_in = None;
+    crate::click::cancel_count_in(app);
+}
+
+pub fn set_loop(app: &mut ClicksMonitorApp, start: u16, end: u16, count_in_bars: u8) {
+    app.rehearsal.loop_region = Some(LoopRegion {
+        start: start.min(end),
+        end: start.max(end),
+        count: 0,
+        count_in_bars,
+    });
+    app.rehearsal.loop_deadline = None;
+}
+
+pub fn clear_loop(app: &mut ClicksMonitorApp) {
+    app.rehearsal.loop_region = None;
+    app.rehearsal.loop_deadline = None;
+}
+
+/// Beat ranges refer to the loaded cue, so drop them when another cue is loaded.
+pub fn on_cue_changed(app: &mut ClicksMonitorApp) {
+    clear_loop(app);
+    app.rehearsal.selection = None;
+    cancel_count_in(app);
+}
+
+/// Schedule the loop seek when the end beat of the loop starts. Called for every BeatData.
+pub fn on_beat(app: &mut ClicksMonitorApp, beat: &BeatState) {
+    let Some(region) = app.rehearsal.loop_region else {
+        return;
+    };
+    if !app.status.transport.running {
+        return;
+    }
+    if beat.beat_idx == region.end {
+        app.rehearsal.loop_deadline = Some(
+            Instant::now() + Duration::from_micros(beat.us_to_next_beat as u64)
+                - SEEK_LEAD.min(Duration::from_micros(beat.us_to_next_beat as u64)),
+        );
+    } else if beat.beat_idx == region.end + 1 && app.rehearsal.loop_deadline.is_none() {
+        // Missed the end beat, go back as soon as possible
+        app.rehearsal.loop_deadline = Some(Instant::now());
+    }
+}
+
+/// Run the loop and count-in timers. Called every frame regardless of which tab is shown.
+pub fn update(app: &mut ClicksMonitorApp) {
+    if !app.status.transport.running {
+        app.rehearsal.loop_deadline = None;
+    }
+    if let Some(deadline) = app.rehearsal.loop_deadline
+        && Instant::now() >= deadline
+    {
+        app.rehearsal.loop_deadline = None;
+        if let Some(region) = &mut app.rehearsal.loop_region {
+            region.count += 1;
+            let region = *region;
+            if region.count_in_bars > 0 && count_in_available(app) {
+                start_count_in(app, region.start, region.count_in_bars);
+            } else {
+                app.udp_client
+                    .send_msg(Request::ControlAction(ControlAction::TransportSeekBeat(
+                        region.start,
+                    )));
+            }
+        }
+    }
+
+    if let Some(count_in) = app.rehearsal.count_in
+        && count_in.done()
+    {
+        app.rehearsal.count_in = None;
+        app.udp_client
+            .send_msg(Request::ControlAction(ControlAction::TransportStart));
+    }
+}
+
+fn bar_beat(cue: &Cue, beat_idx: u16) -> String {
+    cue.beats
+        .get(beat_idx as usize)
+        .map_or("-".to_string(), |b| format!("{}.{}", b.bar_number, b.count))
+}
+
+/// Short description of the loop for the statusbar, if one is set.
+pub fn loop_label(app: &ClicksMonitorApp) -> Option<String> {
+    let region = app.rehearsal.loop_region?;
+    Some(format!(
+        "LOOP {}-{} x{}",
+        bar_beat(&app.status.cue.cue, region.start),
+        bar_beat(&app.status.cue.cue, region.end),
+        region.count
+    ))
+}
+
+pub fn loop_controls(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.vertical(|ui| {
+        if !app.local_memory.security.allow_interaction {
+            ui.disable();
+        }
+        ui.label(egui::RichText::new("Loop").strong());
+        if let Some(count_in) = app.rehearsal.count_in {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    app.theme.cued_prim,
+                    format!("Counting in: {}", count_in.beats_left()),
+                );
+                if ui.small_button("Cancel").clicked() {
+                    cancel_count_in(app);
+                }
+            });
+        }
+        let cue = &app.status.cue.cue;
+        match app.rehearsal.selection {
+            Some((start, end)) => ui.label(format!(
+                "Selected {} to {}",
+                bar_beat(cue, start),
+                bar_beat(cue, end)
+            )),
+            None => ui.label("Drag across beats to select a loop."),
+        };
+        if let Some(region) = app.rehearsal.loop_region {
+            ui.colored_label(
+                app.theme.warn_prim,
+                format!(
+                    "Looping {} to {}, repeated {} time(s)",
+                    bar_beat(cue, region.start),
+                    bar_beat(cue, region.end),
+                    region.count
+                ),
+            );
+        }
+        let available = count_in_available(app);
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(available, |ui| {
+                ui.label("Count-in");
+                let mut bars = app.local_memory.loop_count_in_bars;
+                for (value, text) in [(0, "None"), (1, "1 bar"), (2, "2 bars")] {
+                    ui.selectable_value(&mut bars, value, text);
+                }
+                app.local_memory.loop_count_in_bars = bars;
+                if let Some(region) = &mut app.rehearsal.loop_region {
+                    region.count_in_bars = bars;
+                }
+            });
+            if !available {
+                ui.colored_label(app.theme.warn_prim, MSG_COUNT_IN_UNAVAILABLE);
+            }
+        });
+        ui.horizontal(|ui| {
+            if let Some((start, end)) = app.rehearsal.selection
+                && ui.button("Loop selection").clicked()
+            {
+                set_loop(app, start, end, app.local_memory.loop_count_in_bars);
+            }
+            if app.rehearsal.loop_region.is_some() && ui.button("Clear loop").clicked() {
+                clear_loop(app);
+            }
+        });
+    });
+}
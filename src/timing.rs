@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+
+use common::{
+    beat::Beat,
+    cue::Cue,
+    event::{EventDescription, JumpModeChange, JumpRequirement},
+    local::status::BeatState,
+};
+
+/// Durations of a cue in µs of wall clock time, at the current playrate. Beat lengths already
+/// contain the cue's tempo changes, so only jumps and pauses need to be followed here.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CueTiming {
+    /// All beats played once, front to back.
+    pub total_us: u64,
+    /// Position within the cue.
+    pub elapsed_us: u64,
+    /// Time to the end of the cue, following the jumps that will be taken from here.
+    pub remaining_us: u64,
+    /// A vamp was found ahead, so the remaining time is a lower bound.
+    pub open_ended: bool,
+    /// Pause events ahead, where the transport waits for the operator.
+    pub holds: usize,
+}
+
+fn scale(us: u64, playrate_percent: u16) -> u64 {
+    us * 100 / playrate_percent.max(1) as u64
+}
+
+fn apply_jump_mode(mode: bool, change: JumpModeChange) -> bool {
+    match change {
+        JumpModeChange::None => mode,
+        JumpModeChange::Toggle => !mode,
+        JumpModeChange::On => true,
+        JumpModeChange::Off => false,
+    }
+}
+
+/// Time from the start of the cue to the start of a beat, at normal playrate.
+pub fn beat_offset(cue: &Cue, beat_idx: usize) -> u64 {
+    cue.beats
+        .iter()
+        .take(beat_idx)
+        .map(|b| b.length as u64)
+        .sum()
+}
+
+/// Nominal length of a cue at the given playrate.
+pub fn cue_length(cue: &Cue, playrate_percent: u16) -> u64 {
+    scale(
+        cue.beats.iter().map(|b| b.length as u64).sum(),
+        playrate_percent,
+    )
+}
+
+pub fn cue_timing(
+    cue: &Cue,
+    beat: &BeatState,
+    playrate_percent: u16,
+    jump_mode: bool,
+) -> CueTiming {
+    let mut timing = CueTiming {
+        total_us: cue_length(cue, playrate_percent),
+        ..Default::default()
+    };
+    let beat_idx = beat.beat_idx as usize;
+    if beat_idx >= cue.beats.len() {
+        return timing;
+    }
+
+    let current = cue.beats[beat_idx].length as u64;
+    let into_current = current.saturating_sub(beat.us_to_next_beat as u64);
+    timing.elapsed_us = scale(
+        cue.beats[..beat_idx]
+            .iter()
+            .map(|b| b.length as u64)
+            .sum::<u64>()
+            + into_current,
+        playrate_percent,
+    );
+
+    let events: Vec<(usize, EventDescription)> = cue
+        .events
+        .iter()
+        .filter_map(|e| Some((e.location as usize, e.event?)))
+        .collect();
+    let (ahead, open_ended, holds) = walk_ahead(&cue.beats, &events, beat_idx, jump_mode);
+    timing.remaining_us = scale(current - into_current + ahead, playrate_percent);
+    timing.open_ended = open_ended;
+    timing.holds = holds;
+    timing
+}
+
+/// Length of the beats played after `from`, taking jumps the same way the core would, whether
+/// a vamp was found and how many pauses were passed. Coming back to a beat in the same jump
+/// mode means we are in a vamp that only the operator can leave.
+fn walk_ahead(
+    beats: &[Beat],
+    events: &[(usize, EventDescription)],
+    from: usize,
+    jump_mode: bool,
+) -> (u64, bool, usize) {
+    let mut remaining = 0;
+    let mut open_ended = false;
+    let mut holds = 0;
+    let mut mode = jump_mode;
+    let mut visited = HashSet::new();
+    let mut idx = from + 1;
+    while idx < beats.len() {
+        if !visited.insert((idx, mode)) {
+            open_ended = true;
+            break;
+        }
+        let mut next = idx + 1;
+        for (_, event) in events.iter().filter(|(location, _)| *location == idx) {
+            match *event {
+                EventDescription::JumpEvent {
+                    destination,
+                    requirement,
+                    when_passed,
+                    when_jumped,
+                } => {
+                    let jump = match requirement {
+                        JumpRequirement::None => true,
+                        JumpRequirement::JumpModeOn => mode,
+                        JumpRequirement::JumpModeOff => !mode,
+                    };
+                    if jump {
+                        mode = apply_jump_mode(mode, when_jumped);
+                        next = destination as usize;
+                    } else {
+                        mode = apply_jump_mode(mode, when_passed);
+                    }
+                }
+                EventDescription::PauseEvent { .. } => holds += 1,
+                _ => {}
+            }
+        }
+        if next != idx + 1 {
+            idx = next;
+            continue;
+        }
+        remaining += beats[idx].length as u64;
+        idx = next;
+    }
+    (remaining, open_ended, holds)
+}
+
+/// Format as m:ss, or h:mm:ss for durations of an hour or more.
+pub fn format_us(us: u64) -> String {
+    let seconds = us / 1_000_000;
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::event::PauseEventBehaviour;
+
+    const BEAT_US: u64 = 500_000;
+
+    fn beats(n: usize) -> Vec<Beat> {
+        (0..n)
+            .map(|i| Beat {
+                count: (i % 4) as u8 + 1,
+                bar_number: (i / 4) as u16 + 1,
+                length: BEAT_US as u32,
+            })
+            .collect()
+    }
+
+    fn jump(destination: u16, requirement: JumpRequirement) -> EventDescription {
+        EventDescription::JumpEvent {
+            destination,
+            requirement,
+            when_passed: JumpModeChange::None,
+            when_jumped: JumpModeChange::None,
+        }
+    }
+
+    #[test]
+    fn plays_to_the_end_without_events() {
+        assert_eq!(
+            walk_ahead(&beats(8), &[], 0, false),
+            (7 * BEAT_US, false, 0)
+        );
+        assert_eq!(walk_ahead(&beats(8), &[], 7, false), (0, false, 0));
+    }
+
+    #[test]
+    fn follows_jumps_that_are_taken() {
+        // Skip from beat 2 to beat 6
+        let events = [(2, jump(6, JumpRequirement::None))];
+        assert_eq!(
+            walk_ahead(&beats(8), &events, 0, false),
+            (3 * BEAT_US, false, 0)
+        );
+    }
+
+    #[test]
+    fn skips_jumps_for_the_other_jump_mode() {
+        let events = [(2, jump(6, JumpRequirement::JumpModeOn))];
+        assert_eq!(
+            walk_ahead(&beats(8), &events, 0, false),
+            (7 * BEAT_US, false, 0)
+        );
+        assert_eq!(
+            walk_ahead(&beats(8), &events, 0, true),
+            (3 * BEAT_US, false, 0)
+        );
+    }
+
+    #[test]
+    fn detects_a_vamp() {
+        // Beat 5 always jumps back to beat 2
+        let events = [(5, jump(2, JumpRequirement::None))];
+        let (_, open_ended, _) = walk_ahead(&beats(8), &events, 0, false);
+        assert!(open_ended);
+    }
+
+    #[test]
+    fn leaves_a_repeat_that_turns_off_jump_mode() {
+        // Repeat beats 2-4 once: the jump back turns jump mode off, so it is passed the next time
+        let events = [(
+            5,
+            EventDescription::JumpEvent {
+                destination: 2,
+                requirement: JumpRequirement::JumpModeOn,
+                when_passed: JumpModeChange::None,
+                when_jumped: JumpModeChange::Off,
+            },
+        )];
+        assert_eq!(
+            walk_ahead(&beats(8), &events, 0, true),
+            (10 * BEAT_US, false, 0)
+        );
+    }
+
+    #[test]
+    fn counts_holds_ahead() {
+        let hold = EventDescription::PauseEvent {
+            behaviour: PauseEventBehaviour::Hold,
+        };
+        let events = [(1, hold), (3, hold), (6, hold)];
+        assert_eq!(walk_ahead(&beats(8), &events, 2, false).2, 2);
+    }
+
+    #[test]
+    fn formats_minutes_and_hours() {
+        assert_eq!(format_us(0), "0:00");
+        assert_eq!(format_us(61_999_999), "1:01");
+        assert_eq!(format_us(3_599_000_000), "59:59");
+        assert_eq!(format_us(3_600_000_000), "1:00:00");
+        assert_eq!(format_us(3_725_000_000), "1:02:05");
+    }
+}
@@ -22,8 +22,8 @@ use local_ip_address::local_ip;
 pub struct UdpClient {
     pub local: ConnectionInfo,
     socket: UdpSocket,
-    local_tx: Sender<(Message, usize)>,
-    local_rx: Receiver<(Message, usize)>,
+    local_tx: Sender<(Message, usize, Instant)>,
+    local_rx: Receiver<(Message, usize, Instant)>,
     pub active: bool,
     pub rx_message_tally: HashMap<MessageType, (usize, usize)>,
     pub tx_message_tally: HashMap<RequestType, (usize, usize)>,
@@ -31,7 +31,10 @@ pub struct UdpClient {
 
 impl UdpClient {
     pub fn new() -> UdpClient {
-        let (tx, rx): (Sender<(Message, usize)>, Receiver<(Message, usize)>) = unbounded();
+        let (tx, rx): (
+            Sender<(Message, usize, Instant)>,
+            Receiver<(Message, usize, Instant)>,
+        ) = unbounded();
         UdpClient {
             rx_message_tally: HashMap::new(),
             tx_message_tally: HashMap::new(),
@@ -76,6 +79,8 @@ impl UdpClient {
                 | MessageType::PlaybackData
                 | MessageType::PlaybackHandlerChanged
                 | MessageType::Heartbeat
+                | MessageType::LevelData
+                | MessageType::ClipData
                 | MessageType::Log,
 
             last_contact: Utc::now().timestamp() as u128,
@@ -89,7 +94,7 @@ impl UdpClient {
         Ok(ci)
     }
 
-    pub fn get_receiver(&self) -> Receiver<(Message, usize)> {
+    pub fn get_receiver(&self) -> Receiver<(Message, usize, Instant)> {
         self.local_rx.clone()
     }
 
@@ -131,7 +136,11 @@ impl UdpClient {
                 last_ping_time = Instant::now()
             }
             if last_recv_time.elapsed() > Duration::from_secs(10) {
-                let _ = tx.try_send((Message::Small(SmallMessage::ShutdownOccured), 1));
+                let _ = tx.try_send((
+                    Message::Small(SmallMessage::ShutdownOccured),
+                    1,
+                    Instant::now(),
+                ));
             }
             buf.fill(0);
             match socket.recv(&mut buf) {
@@ -143,7 +152,11 @@ impl UdpClient {
                         match postcard::from_bytes::<LargeMessage>(&buf[1..]) {
                             Ok(msg) => {
                                 //println!("It was a large message: {:?}", msg);
-                                let _ = tx.try_send((Message::Large(msg), packet_len));
+                                let _ = tx.try_send((
+                                    Message::Large(msg),
+                                    packet_len,
+                                    last_recv_time,
+                                ));
                             }
                             Err(err) => {
                                 panic!(
@@ -158,7 +171,11 @@ impl UdpClient {
                         match postcard::from_bytes::<SmallMessage>(&buf[1..]) {
                             Ok(msg) => {
                                 //println!("It was a small message: {:?}", msg);
-                                let _ = tx.try_send((Message::Small(msg), packet_len));
+                                let _ = tx.try_send((
+                                    Message::Small(msg),
+                                    packet_len,
+                                    last_recv_time,
+                                ));
                             }
                             Err(err) => {
                                 panic!(
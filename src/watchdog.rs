@@ -0,0 +1,557 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use common::protocol::message::Heartbeat;
+use egui::{Color32, RichText};
+
+use crate::{app::ClicksMonitorApp, theme::Theme, window::WindowTab};
+
+/// Number of alarm events kept in the log.
+const LOG_LENGTH: usize = 200;
+pub const SNOOZE_MINUTES: [u64; 3] = [5, 15, 60];
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Self::Info, Self::Warning, Self::Critical];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Info => "Info",
+            Self::Warning => "Warning",
+            Self::Critical => "Critical",
+        }
+    }
+
+    pub fn color(&self, theme: &Theme) -> Color32 {
+        match self {
+            Self::Info => theme.neutral_prim,
+            Self::Warning => theme.warn_prim,
+            Self::Critical => theme.err_prim,
+        }
+    }
+}
+
+/// A value the watchdog can raise alarms on.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    CpuUseAudio,
+    ProcessFreqMain,
+    HeartbeatAge,
+    BeatDataAge,
+    JackStopped,
+    QueueDepth,
+    ClockSkew,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 7] = [
+        Self::CpuUseAudio,
+        Self::ProcessFreqMain,
+        Self::HeartbeatAge,
+        Self::BeatDataAge,
+        Self::JackStopped,
+        Self::QueueDepth,
+        Self::ClockSkew,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::CpuUseAudio => "Audio CPU load",
+            Self::ProcessFreqMain => "Processing frequency",
+            Self::HeartbeatAge => "Time since heartbeat",
+            Self::BeatDataAge => "Time since beat data while running",
+            Self::JackStopped => "JACK not running",
+            Self::QueueDepth => "Message queue depth",
+            Self::ClockSkew => "Clock skew to host",
+        }
+    }
+
+    pub fn unit(&self) -> &str {
+        match self {
+            Self::CpuUseAudio => "%",
+            Self::ProcessFreqMain => " kHz",
+            Self::HeartbeatAge | Self::BeatDataAge | Self::ClockSkew => " s",
+            Self::JackStopped | Self::QueueDepth => "",
+        }
+    }
+
+    /// Metrics that are either 1 or 0, so have no threshold to set.
+    pub fn is_flag(&self) -> bool {
+        matches!(self, Self::JackStopped)
+    }
+
+    /// The current value, or None when it is unknown or does not apply.
+    fn value(&self, app: &ClicksMonitorApp) -> Option<f32> {
+        let state = &app.watchdog;
+        let heartbeat = state.last_heartbeat.map(|_| &app.last_heartbeat);
+        match self {
+            Self::CpuUseAudio => heartbeat.map(|h| h.cpu_use_audio),
+            Self::ProcessFreqMain => heartbeat.map(|h| h.process_freq_main as f32 / 1000.0),
+            Self::HeartbeatAge => state
+                .last_heartbeat
+                .filter(|_| app.udp_client.active)
+                .map(|time| time.elapsed().as_secs_f32()),
+            // Counted from when the transport started, if beat data has not arrived since
+            Self::BeatDataAge => state.running_since.map(|since| {
+                state
+                    .last_beat_data
+                    .map_or(since, |time| time.max(since))
+                    .elapsed()
+                    .as_secs_f32()
+            }),
+            Self::JackStopped => heartbeat.map(|_| {
+                if app.status.jack_status.running {
+                    0.0
+                } else {
+                    1.0
+                }
+            }),
+            Self::QueueDepth => Some(state.queue_depth as f32),
+            Self::ClockSkew => state.clock_skew,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+impl Comparison {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Above => "above",
+            Self::Below => "below",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct AlarmRule {
+    pub enabled: bool,
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub threshold: f32,
+    /// How far back past the threshold the value must go before the alarm clears.
+    pub hysteresis: f32,
+    /// How long the threshold must be crossed before the alarm is raised, in seconds.
+    pub delay_s: f32,
+    pub severity: Severity,
+}
+
+impl AlarmRule {
+    pub fn new(
+        metric: Metric,
+        comparison: Comparison,
+        threshold: f32,
+        hysteresis: f32,
+        severity: Severity,
+    ) -> Self {
+        Self {
+            enabled: true,
+            metric,
+            comparison,
+            threshold,
+            hysteresis,
+            delay_s: 0.0,
+            severity,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        if self.metric.is_flag() {
+            return self.metric.name().to_string();
+        }
+        format!(
+            "{} {} {}{}",
+            self.metric.name(),
+            self.comparison.name(),
+            self.threshold,
+            self.metric.unit()
+        )
+    }
+
+    fn exceeded(&self, value: f32) -> bool {
+        match self.comparison {
+            Comparison::Above => value > self.threshold,
+            Comparison::Below => value < self.threshold,
+        }
+    }
+
+    fn recovered(&self, value: f32) -> bool {
+        match self.comparison {
+            Comparison::Above => value <= self.threshold - self.hysteresis,
+            Comparison::Below => value >= self.threshold + self.hysteresis,
+        }
+    }
+}
+
+/// The rules replacing the fixed limits the statusbar used to have.
+pub fn default_rules() -> Vec<AlarmRule> {
+    vec![
+        AlarmRule::new(
+            Metric::CpuUseAudio,
+            Comparison::Above,
+            80.0,
+            5.0,
+            Severity::Warning,
+        ),
+        AlarmRule::new(
+            Metric::ProcessFreqMain,
+            Comparison::Below,
+            10.0,
+            0.5,
+            Severity::Critical,
+        ),
+        AlarmRule::new(
+            Metric::HeartbeatAge,
+            Comparison::Above,
+            5.0,
+            1.0,
+            Severity::Critical,
+        ),
+        AlarmRule::new(
+            Metric::BeatDataAge,
+            Comparison::Above,
+            3.0,
+            0.5,
+            Severity::Critical,
+        ),
+        AlarmRule::new(
+            Metric::JackStopped,
+            Comparison::Above,
+            0.5,
+            0.0,
+            Severity::Critical,
+        ),
+        AlarmRule::new(
+            Metric::QueueDepth,
+            Comparison::Above,
+            16.0,
+            8.0,
+            Severity::Warning,
+        ),
+        AlarmRule::new(
+            Metric::ClockSkew,
+            Comparison::Above,
+            5.0,
+            1.0,
+            Severity::Warning,
+        ),
+    ]
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    pub rules: Vec<AlarmRule>,
+    /// Show a window over everything while a critical alarm is unacknowledged.
+    pub popup_critical: bool,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            popup_critical: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AlarmState {
+    pub active: bool,
+    /// When the alarm was raised.
+    pub since: Option<Instant>,
+    /// When the threshold was first crossed, while waiting out the rule's delay.
+    pending: Option<Instant>,
+    pub value: Option<f32>,
+    pub acknowledged: bool,
+    pub snoozed_until: Option<Instant>,
+}
+
+impl AlarmState {
+    pub fn snoozed(&self) -> bool {
+        self.snoozed_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Active, and neither acknowledged nor snoozed.
+    pub fn needs_attention(&self) -> bool {
+        self.active && !self.acknowledged && !self.snoozed()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlarmEventKind {
+    Raised,
+    Cleared,
+    Acknowledged,
+    Snoozed(u64),
+    /// Queued messages dropped because the queue was too far behind.
+    Discarded(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct AlarmEvent {
+    /// Unix time in ms.
+    pub time: i64,
+    pub rule: String,
+    pub severity: Severity,
+    pub value: Option<f32>,
+    pub kind: AlarmEventKind,
+}
+
+/// Alarm states, indexed like the rules in [`WatchdogSettings`], and what the metrics are
+/// measured from.
+#[derive(Default)]
+pub struct WatchdogState {
+    pub alarms: Vec<AlarmState>,
+    pub log: VecDeque<AlarmEvent>,
+    last_heartbeat: Option<Instant>,
+    last_beat_data: Option<Instant>,
+    running_since: Option<Instant>,
+    /// Difference between the host clock and ours at the last heartbeat, in seconds.
+    clock_skew: Option<f32>,
+    /// Messages waiting at the start of this frame.
+    queue_depth: usize,
+}
+
+impl WatchdogState {
+    fn push(&mut self, event: AlarmEvent) {
+        if self.log.len() >= LOG_LENGTH {
+            self.log.pop_front();
+        }
+        self.log.push_back(event);
+    }
+
+    fn push_event(&mut self, rule: &AlarmRule, value: Option<f32>, kind: AlarmEventKind) {
+        self.push(AlarmEvent {
+            time: chrono::Utc::now().timestamp_millis(),
+            rule: rule.describe(),
+            severity: rule.severity,
+            value,
+            kind,
+        });
+    }
+
+    /// Log messages the app dropped from a queue over `limit`. This does not depend on any rule.
+    pub fn log_discarded(&mut self, count: usize, limit: usize) {
+        self.push(AlarmEvent {
+            time: chrono::Utc::now().timestamp_millis(),
+            rule: format!("Message queue over {}", limit),
+            severity: Severity::Warning,
+            value: None,
+            kind: AlarmEventKind::Discarded(count),
+        });
+    }
+
+    /// Forget an alarm's state, logging that it cleared if it was active.
+    fn reset(&mut self, idx: usize, rule: &AlarmRule) {
+        let Some(alarm) = self.alarms.get_mut(idx) else {
+            return;
+        };
+        let (active, value) = (alarm.active, alarm.value);
+        *alarm = AlarmState::default();
+        if active {
+            self.push_event(rule, value, AlarmEventKind::Cleared);
+        }
+    }
+}
+
+pub fn on_heartbeat(app: &mut ClicksMonitorApp, heartbeat: &Heartbeat) {
+    let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+    app.watchdog.last_heartbeat = Some(Instant::now());
+    // The host only sends whole seconds
+    app.watchdog.clock_skew = Some((now - heartbeat.system_time as f64).abs() as f32);
+}
+
+pub fn on_beat_data(app: &mut ClicksMonitorApp) {
+    app.watchdog.last_beat_data = Some(Instant::now());
+}
+
+/// Evaluate every rule. Called every frame with the queue depth before it is drained.
+pub fn update(app: &mut ClicksMonitorApp, queue_depth: usize) {
+    let now = Instant::now();
+    let state = &mut app.watchdog;
+    state.queue_depth = queue_depth;
+    if !app.status.transport.running {
+        state.running_since = None;
+    } else if state.running_since.is_none() {
+        state.running_since = Some(now);
+    }
+
+    let rules = &app.local_memory.watchdog.rules;
+    let values: Vec<Option<f32>> = rules
+        .iter()
+        .map(|rule| rule.enabled.then(|| rule.metric.value(app)).flatten())
+        .collect();
+    let state = &mut app.watchdog;
+    state.alarms.resize_with(rules.len(), AlarmState::default);
+    for ((rule, value), idx) in rules.iter().zip(values).zip(0..) {
+        let alarm = &mut state.alarms[idx];
+        alarm.value = value;
+        if !alarm.snoozed() {
+            alarm.snoozed_until = None;
+        }
+        if alarm.active {
+            if value.is_none_or(|v| rule.recovered(v)) {
+                *alarm = AlarmState {
+                    snoozed_until: alarm.snoozed_until,
+                    ..AlarmState::default()
+                };
+                state.push_event(rule, value, AlarmEventKind::Cleared);
+            }
+        } else if value.is_some_and(|v| rule.exceeded(v)) {
+            let pending = *alarm.pending.get_or_insert(now);
+            if now.duration_since(pending).as_secs_f32() >= rule.delay_s {
+                alarm.active = true;
+                alarm.since = Some(now);
+                alarm.pending = None;
+                state.push_event(rule, value, AlarmEventKind::Raised);
+            }
+        } else {
+            alarm.pending = None;
+        }
+    }
+}
+
+pub fn acknowledge(app: &mut ClicksMonitorApp, idx: usize) {
+    let Some(rule) = app.local_memory.watchdog.rules.get(idx) else {
+        return;
+    };
+    let alarm = &mut app.watchdog.alarms[idx];
+    if alarm.active && !alarm.acknowledged {
+        alarm.acknowledged = true;
+        let value = alarm.value;
+        app.watchdog
+            .push_event(rule, value, AlarmEventKind::Acknowledged);
+    }
+}
+
+pub fn acknowledge_all(app: &mut ClicksMonitorApp) {
+    for idx in 0..app.watchdog.alarms.len() {
+        acknowledge(app, idx);
+    }
+}
+
+/// Keep a rule from demanding attention for a while. It is still evaluated and logged.
+pub fn snooze(app: &mut ClicksMonitorApp, idx: usize, minutes: u64) {
+    let Some(rule) = app.local_memory.watchdog.rules.get(idx) else {
+        return;
+    };
+    let alarm = &mut app.watchdog.alarms[idx];
+    alarm.snoozed_until = Some(Instant::now() + Duration::from_secs(minutes * 60));
+    let value = alarm.value;
+    app.watchdog
+        .push_event(rule, value, AlarmEventKind::Snoozed(minutes));
+}
+
+/// Start a rule's alarm over after it was changed to watch something else. `before` is the rule
+/// as it was, which the cleared alarm is logged under.
+pub fn reset_alarm(app: &mut ClicksMonitorApp, idx: usize, before: &AlarmRule) {
+    app.watchdog.reset(idx, before);
+}
+
+pub fn remove_rule(app: &mut ClicksMonitorApp, idx: usize) {
+    let rule = app.local_memory.watchdog.rules.remove(idx);
+    app.watchdog.reset(idx, &rule);
+    if idx < app.watchdog.alarms.len() {
+        app.watchdog.alarms.remove(idx);
+    }
+}
+
+pub fn reset_rules(app: &mut ClicksMonitorApp) {
+    let rules = std::mem::replace(&mut app.local_memory.watchdog.rules, default_rules());
+    for (idx, rule) in rules.iter().enumerate() {
+        app.watchdog.reset(idx, rule);
+    }
+    app.watchdog.alarms.clear();
+}
+
+/// Rules with their alarm state.
+pub fn alarms(app: &ClicksMonitorApp) -> impl Iterator<Item = (usize, &AlarmRule, &AlarmState)> {
+    app.local_memory
+        .watchdog
+        .rules
+        .iter()
+        .zip(&app.watchdog.alarms)
+        .enumerate()
+        .map(|(idx, (rule, alarm))| (idx, rule, alarm))
+}
+
+/// The most severe alarm that needs attention, and how many alarms are active.
+pub fn summary(app: &ClicksMonitorApp) -> Option<(Option<Severity>, usize)> {
+    let active = alarms(app).filter(|(_, _, alarm)| alarm.active).count();
+    (active > 0).then(|| {
+        let worst = alarms(app)
+            .filter(|(_, _, alarm)| alarm.needs_attention())
+            .map(|(_, rule, _)| rule.severity)
+            .max();
+        (worst, active)
+    })
+}
+
+/// The most severe active alarm on a metric, acknowledged or not.
+pub fn metric_alarm(app: &ClicksMonitorApp, metric: Metric) -> Option<Severity> {
+    alarms(app)
+        .filter(|(_, rule, alarm)| rule.metric == metric && alarm.active)
+        .map(|(_, rule, _)| rule.severity)
+        .max()
+}
+
+/// Thresholds of the enabled rules on a metric, for drawing on plots.
+pub fn thresholds(app: &ClicksMonitorApp, metric: Metric) -> Vec<(f32, String)> {
+    app.local_memory
+        .watchdog
+        .rules
+        .iter()
+        .filter(|rule| rule.enabled && rule.metric == metric)
+        .map(|rule| (rule.threshold, rule.severity.name().to_string()))
+        .collect()
+}
+
+/// Show unacknowledged critical alarms in a window over everything else.
+pub fn popup(app: &mut ClicksMonitorApp, ctx: &egui::Context) {
+    if !app.local_memory.watchdog.popup_critical {
+        return;
+    }
+    let critical: Vec<(usize, String)> = alarms(app)
+        .filter(|(_, rule, alarm)| rule.severity == Severity::Critical && alarm.needs_attention())
+        .map(|(idx, rule, _)| (idx, rule.describe()))
+        .collect();
+    if critical.is_empty() {
+        return;
+    }
+    crate::widget::popup::new("Alarm")
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 64.0])
+        .show(ctx, |ui| {
+            for (idx, description) in critical {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new(description)
+                            .heading()
+                            .color(app.theme.err_prim),
+                    );
+                    if ui.button("Acknowledge").clicked() {
+                        acknowledge(app, idx);
+                    }
+                });
+            }
+            if ui.button("Show alarms").clicked() {
+                app.local_memory.current_tab = WindowTab::SystemAlarms;
+            }
+        });
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+use common::local::status::ChannelLevel;
+use egui::{Rect, Response, Sense, Ui, Vec2};
+
+use crate::theme::Theme;
+
+const METER_FLOOR_DB: f32 = -60.0;
+const METER_CEIL_DB: f32 = 0.0;
+const CLIP_THRESHOLD_DB: f32 = -0.1;
+const FALLOFF_DB_PER_SEC: f32 = 24.0;
+const PEAK_HOLD: Duration = Duration::from_millis(1500);
+
+/// Display state for one level meter. Incoming levels are in dBFS, and the meter keeps the
+/// falloff, peak hold and clip latch between level messages.
+#[derive(Clone, Copy, Debug)]
+pub struct MeterState {
+    peak: f32,
+    rms: f32,
+    peak_hold: f32,
+    peak_hold_time: Instant,
+    clipped: bool,
+    last_update: Instant,
+}
+
+impl Default for MeterState {
+    fn default() -> Self {
+        Self {
+            peak: METER_FLOOR_DB,
+            rms: METER_FLOOR_DB,
+            peak_hold: METER_FLOOR_DB,
+            peak_hold_time: Instant::now(),
+            clipped: false,
+            last_update: Instant::now(),
+        }
+    }
+}
+
+impl MeterState {
+    pub fn feed(&mut self, level: ChannelLevel) {
+        self.decay();
+        self.peak = self.peak.max(level.peak);
+        self.rms = self.rms.max(level.rms);
+        if level.peak >= self.peak_hold || self.peak_hold_time.elapsed() > PEAK_HOLD {
+            self.peak_hold = level.peak;
+            self.peak_hold_time = Instant::now();
+        }
+        if level.peak >= CLIP_THRESHOLD_DB {
+            self.clipped = true;
+        }
+    }
+
+    /// Let the bars fall towards the floor according to time passed since the last call.
+    pub fn decay(&mut self) {
+        let fall = self.last_update.elapsed().as_secs_f32() * FALLOFF_DB_PER_SEC;
+        self.last_update = Instant::now();
+        self.peak = (self.peak - fall).max(METER_FLOOR_DB);
+        self.rms = (self.rms - fall).max(METER_FLOOR_DB);
+        if self.peak_hold_time.elapsed() > PEAK_HOLD {
+            self.peak_hold = (self.peak_hold - fall).max(self.peak);
+        }
+    }
+
+    pub fn reset_clip(&mut self) {
+        self.clipped = false;
+    }
+}
+
+fn db_to_fraction(db: f32) -> f32 {
+    ((db - METER_FLOOR_DB) / (METER_CEIL_DB - METER_FLOOR_DB)).clamp(0.0, 1.0)
+}
+
+/// Draw a vertical peak/RMS meter with peak hold line and clip indicator on top. Clicking the
+/// meter resets the clip indicator.
+pub fn vertical_meter(ui: &mut Ui, state: &mut MeterState, theme: &Theme, size: Vec2) -> Response {
+    state.decay();
+
+    let (resp, p) = ui.allocate_painter(size, Sense::click());
+    let clip_height = size.x.min(8.0);
+    let clip_rect = Rect::from_min_size(resp.rect.min, Vec2::new(size.x, clip_height));
+    let bar_rect = Rect::from_min_max(
+        resp.rect.min + Vec2::new(0.0, clip_height + 2.0),
+        resp.rect.max,
+    );
+
+    p.rect_filled(
+        clip_rect,
+        1.0,
+        if state.clipped {
+            theme.err_prim
+        } else {
+            theme.base_ex
+        },
+    );
+    p.rect_filled(bar_rect, 1.0, theme.base_ex);
+
+    let level_rect = |db: f32| {
+        Rect::from_min_max(
+            bar_rect.left_bottom() - Vec2::new(0.0, bar_rect.height() * db_to_fraction(db)),
+            bar_rect.right_bottom(),
+        )
+    };
+    let color = if state.peak >= CLIP_THRESHOLD_DB {
+        theme.err_prim
+    } else if state.peak > -6.0 {
+        theme.warn_prim
+    } else {
+        theme.active_prim
+    };
+    p.rect_filled(level_rect(state.peak), 0.0, color.gamma_multiply(0.5));
+    p.rect_filled(level_rect(state.rms), 0.0, color);
+
+    if state.peak_hold > METER_FLOOR_DB {
+        let y = bar_rect.bottom() - bar_rect.height() * db_to_fraction(state.peak_hold);
+        p.hline(
+            bar_rect.x_range(),
+            y,
+            egui::Stroke::new(2.0, theme.neutral_prim),
+        );
+    }
+
+    if resp.clicked() {
+        state.reset_clip();
+    }
+
+    resp.on_hover_text(format!(
+        "Peak {:.1} dBFS\nRMS {:.1} dBFS\nClick to reset clip indicator",
+        state.peak, state.rms
+    ))
+}
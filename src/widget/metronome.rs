@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use common::{cue::Cue, local::status::BeatState};
+use egui::{Response, Sense, Ui, Vec2};
+
+use crate::theme::Theme;
+
+const FLASH_LENGTH: Duration = Duration::from_millis(150);
+/// Weight of the newest sample in the running jitter average.
+const JITTER_SMOOTHING: f32 = 0.1;
+/// Beat data arriving this much later than predicted is not used for the jitter average, as
+/// the transport was most likely stopped, seeked or changed tempo.
+const JITTER_LIMIT: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug)]
+struct Flash {
+    time: Instant,
+    beat_idx: u16,
+    downbeat: bool,
+}
+
+/// Flash timing for the visual metronome. Each BeatData predicts when the next beat starts from
+/// `us_to_next_beat` counted from when the packet was received, and the flash is shown at that
+/// time. Network latency is not compensated, as the core offers no round trip to measure it
+/// with. Beats that were not predicted flash when their data arrives instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetronomeState {
+    beat_idx: u16,
+    /// The predicted start of the next beat.
+    next: Option<Flash>,
+    /// When the next beat's data is expected to arrive if there were no delay.
+    expected_arrival: Option<Instant>,
+    last_flash: Option<Flash>,
+    /// Average lateness of beat data compared to the previous prediction, in seconds.
+    jitter: f32,
+}
+
+impl MetronomeState {
+    pub fn feed(&mut self, beat: &BeatState, cue: &Cue, running: bool, received: Instant) {
+        let now = Instant::now();
+        if !running {
+            self.next = None;
+            self.expected_arrival = None;
+            self.beat_idx = beat.beat_idx;
+            return;
+        }
+
+        if beat.beat_idx != self.beat_idx {
+            if let Some(expected) = self.expected_arrival
+                && received.saturating_duration_since(expected) < JITTER_LIMIT
+            {
+                let late = received.saturating_duration_since(expected).as_secs_f32();
+                self.jitter += (late - self.jitter) * JITTER_SMOOTHING;
+            }
+            // Nothing was predicted for this beat, so show it now
+            self.update();
+            let shown = |flash: Option<Flash>| flash.is_some_and(|f| f.beat_idx == beat.beat_idx);
+            if !shown(self.last_flash) && !shown(self.next) {
+                self.last_flash = Some(Flash {
+                    time: now,
+                    beat_idx: beat.beat_idx,
+                    downbeat: beat.beat.count == 1,
+                });
+            }
+            self.beat_idx = beat.beat_idx;
+        }
+
+        let to_next = Duration::from_micros(beat.us_to_next_beat as u64);
+        let downbeat = cue
+            .beats
+            .get(beat.next_beat_idx as usize)
+            .is_some_and(|b| b.count == 1);
+        self.expected_arrival = Some(received + to_next);
+        self.next = Some(Flash {
+            time: received + to_next,
+            beat_idx: beat.next_beat_idx,
+            downbeat,
+        });
+    }
+
+    /// Move a due prediction into the current flash.
+    fn update(&mut self) {
+        if let Some(next) = self.next
+            && Instant::now() >= next.time
+        {
+            self.last_flash = Some(next);
+            self.next = None;
+        }
+    }
+
+    /// Strength of the current flash from 1 down to 0, and whether it is a downbeat.
+    pub fn flash(&mut self) -> Option<(f32, bool)> {
+        self.update();
+        let flash = self.last_flash?;
+        let elapsed = flash.time.elapsed();
+        (elapsed < FLASH_LENGTH).then(|| {
+            (
+                1.0 - elapsed.as_secs_f32() / FLASH_LENGTH.as_secs_f32(),
+                flash.downbeat,
+            )
+        })
+    }
+
+    /// Average lateness of beat data compared to the previous prediction.
+    pub fn jitter(&self) -> Duration {
+        Duration::from_secs_f32(self.jitter)
+    }
+}
+
+/// Draw a round lamp that lights up on every beat, in the warning colour on downbeats.
+pub fn beat_flash(ui: &mut Ui, state: &mut MetronomeState, theme: &Theme, size: Vec2) -> Response {
+    let (resp, p) = ui.allocate_painter(size, Sense::hover());
+    let color = match state.flash() {
+        Some((strength, downbeat)) => theme.base_wk.lerp_to_gamma(
+            if downbeat {
+                theme.warn_prim
+            } else {
+                theme.active_prim
+            },
+            strength,
+        ),
+        None => theme.base_wk,
+    };
+    p.circle_filled(resp.rect.center(), size.min_elem() / 2.0, color);
+    resp
+}
@@ -1,3 +1,7 @@
 pub mod cassette;
+pub mod meter;
+pub mod metronome;
+pub mod plot;
 pub mod popup;
 pub mod textentry;
+pub mod waveform;
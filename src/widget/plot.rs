@@ -0,0 +1,491 @@
+use std::collections::VecDeque;
+
+use chrono::{Local, TimeZone};
+use egui::{Align2, Color32, FontId, Pos2, Rect, Response, Sense, Stroke, Ui, Vec2, pos2, vec2};
+
+use crate::theme::Theme;
+
+const AXIS_MARGIN: Vec2 = Vec2::new(56.0, 20.0);
+const MIN_SPAN_MS: f64 = 5_000.0;
+/// Time axis steps in seconds, from which the one giving a readable number of ticks is used.
+const TIME_STEPS: [i64; 14] = [
+    1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600, 7200,
+];
+
+/// A round step for axis ticks, giving roughly `target` ticks over `range`.
+pub fn tick_step(range: f32, target: f32) -> f32 {
+    let raw = (range / target).max(f32::EPSILON);
+    let magnitude = 10f32.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// A value over a period of time. Downsampled samples cover several original ones.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// Unix time in ms of the first value in the sample.
+    pub time: i64,
+    pub min: f32,
+    pub mean: f32,
+    pub max: f32,
+    pub count: u32,
+}
+
+/// Timestamped values for plotting. Old samples are merged in pairs once the series grows past
+/// its point limit, so long histories keep their peaks at a coarser resolution.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct TimeSeries {
+    samples: VecDeque<Sample>,
+}
+
+impl TimeSeries {
+    pub fn push(&mut self, time: i64, value: f32) {
+        self.samples.push_back(Sample {
+            time,
+            min: value,
+            mean: value,
+            max: value,
+            count: 1,
+        });
+    }
+
+    /// Drop samples from before `since`, and halve the resolution of the older half of the
+    /// series while it has more than `max_points` samples.
+    pub fn trim(&mut self, since: i64, max_points: usize) {
+        while self.samples.front().is_some_and(|s| s.time < since) {
+            self.samples.pop_front();
+        }
+        if self.samples.len() <= max_points.max(2) {
+            return;
+        }
+        let older = self.samples.len() / 2;
+        let mut merged: VecDeque<Sample> = self
+            .samples
+            .drain(..older)
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|pair| {
+                pair.iter().skip(1).fold(pair[0], |a, b| Sample {
+                    time: a.time,
+                    min: a.min.min(b.min),
+                    mean: (a.mean * a.count as f32 + b.mean * b.count as f32)
+                        / (a.count + b.count) as f32,
+                    max: a.max.max(b.max),
+                    count: a.count + b.count,
+                })
+            })
+            .collect();
+        merged.append(&mut self.samples);
+        self.samples = merged;
+    }
+
+    pub fn samples(&self) -> &VecDeque<Sample> {
+        &self.samples
+    }
+
+    /// The sample closest in time, if any.
+    fn nearest(&self, time: i64) -> Option<&Sample> {
+        let idx = self.samples.partition_point(|s| s.time < time);
+        [idx.checked_sub(1), Some(idx)]
+            .into_iter()
+            .flatten()
+            .filter_map(|i| self.samples.get(i))
+            .min_by_key(|s| (s.time - time).abs())
+    }
+}
+
+/// The visible time window. Plots sharing a view zoom and pan together.
+#[derive(Clone, Copy, Debug)]
+pub struct PlotView {
+    /// Length of the visible window in ms.
+    pub span: f64,
+    /// Unix time in ms of the right edge, or None to follow the current time.
+    pub end: Option<f64>,
+    /// Longest span zooming out allows, in ms.
+    pub max_span: Option<f64>,
+}
+
+impl Default for PlotView {
+    fn default() -> Self {
+        Self {
+            span: 5.0 * 60_000.0,
+            end: None,
+            max_span: None,
+        }
+    }
+}
+
+impl PlotView {
+    pub fn following(&self) -> bool {
+        self.end.is_none()
+    }
+
+    pub fn follow(&mut self) {
+        self.end = None;
+    }
+
+    /// Limit zooming out to `max_span` ms, shrinking the current span if needed.
+    pub fn limit_span(&mut self, max_span: f64) {
+        self.max_span = Some(max_span.max(MIN_SPAN_MS));
+        self.span = self.clamp_span(self.span);
+    }
+
+    fn clamp_span(&self, span: f64) -> f64 {
+        span.clamp(MIN_SPAN_MS, self.max_span.unwrap_or(f64::MAX))
+    }
+
+    fn range(&self, now: f64) -> (f64, f64) {
+        let end = self.end.unwrap_or(now);
+        (end - self.span, end)
+    }
+}
+
+struct Threshold {
+    value: f32,
+    label: String,
+}
+
+struct Series<'a> {
+    name: String,
+    data: &'a TimeSeries,
+    color: Color32,
+}
+
+/// A line plot of one or more time series against wall clock time. Drag to pan, ctrl+scroll or
+/// pinch to zoom and double-click to go back to following the current time. Hovering shows the
+/// values at the pointer.
+pub struct TimePlot<'a> {
+    title: String,
+    unit: String,
+    series: Vec<Series<'a>>,
+    thresholds: Vec<Threshold>,
+    y_range: Option<(f32, f32)>,
+    height: f32,
+}
+
+impl<'a> TimePlot<'a> {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            unit: String::new(),
+            series: vec![],
+            thresholds: vec![],
+            y_range: None,
+            height: 160.0,
+        }
+    }
+
+    pub fn unit(mut self, unit: &str) -> Self {
+        self.unit = unit.to_string();
+        self
+    }
+
+    pub fn series(mut self, name: &str, data: &'a TimeSeries, color: Color32) -> Self {
+        self.series.push(Series {
+            name: name.to_string(),
+            data,
+            color,
+        });
+        self
+    }
+
+    /// Horizontal lines at the given values, with labels.
+    pub fn thresholds(mut self, thresholds: impl IntoIterator<Item = (f32, String)>) -> Self {
+        self.thresholds.extend(
+            thresholds
+                .into_iter()
+                .map(|(value, label)| Threshold { value, label }),
+        );
+        self
+    }
+
+    /// Fix the value axis instead of fitting it to the visible data.
+    pub fn y_range(mut self, min: f32, max: f32) -> Self {
+        self.y_range = Some((min, max));
+        self
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui, view: &mut PlotView, theme: &Theme) -> Response {
+        let (resp, p) = ui.allocate_painter(
+            vec2(ui.available_width(), self.height),
+            Sense::click_and_drag(),
+        );
+        let plot = Rect::from_min_max(
+            resp.rect.min + vec2(AXIS_MARGIN.x, AXIS_MARGIN.y),
+            resp.rect.max - vec2(8.0, AXIS_MARGIN.y),
+        );
+        let now = chrono::Utc::now().timestamp_millis() as f64;
+
+        // Zoom and pan
+        if resp.hovered() {
+            let zoom = ui.input(|i| i.zoom_delta());
+            if zoom != 1.0 {
+                let (start, end) = view.range(now);
+                let anchor = resp.hover_pos().map_or(end, |pos| {
+                    start + ((pos.x - plot.left()) / plot.width()) as f64 * view.span
+                });
+                let span = view.clamp_span(view.span / zoom as f64);
+                let zoom = view.span / span;
+                view.span = span;
+                // Keep the time under the pointer in place, unless following the current time
+                if view.end.is_some() {
+                    let new_end = anchor + (end - anchor) / zoom;
+                    view.end = (new_end < now).then_some(new_end);
+                }
+            }
+        }
+        if resp.dragged() {
+            let (_, end) = view.range(now);
+            let moved = -(resp.drag_delta().x / plot.width()) as f64 * view.span;
+            view.end = Some(end + moved).filter(|end| *end < now);
+        }
+        if resp.double_clicked() {
+            view.follow();
+        }
+        let (start, end) = view.range(now);
+        let to_x = |time: f64| plot.left() + ((time - start) / view.span) as f32 * plot.width();
+
+        // Value range from the visible samples
+        let visible = |data: &'a TimeSeries| {
+            data.samples()
+                .iter()
+                .filter(move |s| s.time as f64 >= start && s.time as f64 <= end)
+        };
+        let (y_min, y_max) = self.y_range.unwrap_or_else(|| {
+            let (min, max) = self
+                .series
+                .iter()
+                .flat_map(|s| visible(s.data))
+                .fold((f32::MAX, f32::MIN), |(min, max), s| {
+                    (min.min(s.min), max.max(s.max))
+                });
+            let min = self.thresholds.iter().fold(min, |m, t| m.min(t.value));
+            let max = self.thresholds.iter().fold(max, |m, t| m.max(t.value));
+            if min > max {
+                (0.0, 1.0)
+            } else {
+                let pad = ((max - min) * 0.1).max(max.abs() * 0.05).max(0.5);
+                (
+                    (min - pad).max(if min >= 0.0 { 0.0 } else { f32::MIN }),
+                    max + pad,
+                )
+            }
+        });
+        let to_y = |value: f32| {
+            plot.bottom() - (value - y_min) / (y_max - y_min).max(f32::EPSILON) * plot.height()
+        };
+
+        p.rect_filled(resp.rect, 0.0, Color32::BLACK);
+        p.text(
+            resp.rect.left_top() + vec2(4.0, 2.0),
+            Align2::LEFT_TOP,
+            &self.title,
+            FontId::proportional(14.0),
+            theme.neutral_prim,
+        );
+        let grid = Stroke::new(1.0, theme.base_wk);
+        let font = FontId::monospace(11.0);
+
+        // Value axis
+        let step = tick_step(y_max - y_min, 4.0);
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        let mut tick = (y_min / step).ceil() * step;
+        while tick <= y_max {
+            p.hline(plot.x_range(), to_y(tick), grid);
+            p.text(
+                pos2(plot.left() - 4.0, to_y(tick)),
+                Align2::RIGHT_CENTER,
+                format!("{:.*}{}", decimals, tick, self.unit),
+                font.clone(),
+                theme.neutral_prim,
+            );
+            tick += step;
+        }
+
+        // Time axis, in local time
+        let step_s = TIME_STEPS
+            .iter()
+            .copied()
+            .find(|step| view.span / (*step as f64 * 1000.0) <= 8.0)
+            .unwrap_or(14400);
+        let step_ms = step_s * 1000;
+        let mut tick = (start as i64 / step_ms + 1) * step_ms;
+        while (tick as f64) < end {
+            let x = to_x(tick as f64);
+            p.vline(x, plot.y_range(), grid);
+            p.text(
+                pos2(x, plot.bottom() + 2.0),
+                Align2::CENTER_TOP,
+                format_time(tick),
+                font.clone(),
+                theme.neutral_prim,
+            );
+            tick += step_ms;
+        }
+
+        // Thresholds
+        for threshold in &self.thresholds {
+            if threshold.value < y_min || threshold.value > y_max {
+                continue;
+            }
+            let y = to_y(threshold.value);
+            p.hline(plot.x_range(), y, Stroke::new(1.0, theme.warn_prim));
+            p.text(
+                pos2(plot.right() - 2.0, y - 1.0),
+                Align2::RIGHT_BOTTOM,
+                &threshold.label,
+                font.clone(),
+                theme.warn_prim,
+            );
+        }
+
+        // Series, with the min to max range of downsampled samples as a band
+        let clipped = p.with_clip_rect(plot);
+        for series in &self.series {
+            let samples: Vec<&Sample> = visible(series.data).collect();
+            for s in samples.iter().filter(|s| s.count > 1 && s.max > s.min) {
+                let x = to_x(s.time as f64);
+                clipped.vline(
+                    x,
+                    to_y(s.max)..=to_y(s.min),
+                    Stroke::new(1.0, series.color.gamma_multiply(0.4)),
+                );
+            }
+            let points: Vec<Pos2> = samples
+                .iter()
+                .map(|s| pos2(to_x(s.time as f64), to_y(s.mean)))
+                .collect();
+            if points.len() == 1 {
+                clipped.circle_filled(points[0], 2.0, series.color);
+            }
+            clipped.line(points, Stroke::new(1.5, series.color));
+        }
+
+        if !view.following() {
+            p.text(
+                resp.rect.right_top() + vec2(-4.0, 2.0),
+                Align2::RIGHT_TOP,
+                "Paused, double-click to follow",
+                FontId::proportional(12.0),
+                theme.warn_prim,
+            );
+        }
+
+        // Hover readout
+        if let Some(pos) = resp.hover_pos()
+            && plot.contains(pos)
+        {
+            let time = start + ((pos.x - plot.left()) / plot.width()) as f64 * view.span;
+            p.vline(pos.x, plot.y_range(), Stroke::new(1.0, theme.neutral_prim));
+            let mut text = format_time(time as i64);
+            for series in &self.series {
+                if let Some(s) = series.data.nearest(time as i64) {
+                    clipped.circle_filled(
+                        pos2(to_x(s.time as f64), to_y(s.mean)),
+                        3.0,
+                        series.color,
+                    );
+                    text += &if s.count > 1 {
+                        format!(
+                            "\n{}: {:.2}{} ({:.2} to {:.2})",
+                            series.name, s.mean, self.unit, s.min, s.max
+                        )
+                    } else {
+                        format!("\n{}: {:.2}{}", series.name, s.mean, self.unit)
+                    };
+                }
+            }
+            return resp.on_hover_text_at_pointer(text);
+        }
+        resp
+    }
+}
+
+/// Local wall clock time of a unix time in ms.
+pub fn format_time(time_ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(time_ms)
+        .single()
+        .map_or("--:--:--".to_string(), |t| t.format("%H:%M:%S").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(values: &[f32]) -> TimeSeries {
+        let mut series = TimeSeries::default();
+        for (i, value) in values.iter().enumerate() {
+            series.push(i as i64 * 1000, *value);
+        }
+        series
+    }
+
+    #[test]
+    fn drops_samples_before_the_start() {
+        let mut series = series(&[1.0, 2.0, 3.0, 4.0]);
+        series.trim(2000, 100);
+        let times: Vec<i64> = series.samples().iter().map(|s| s.time).collect();
+        assert_eq!(times, vec![2000, 3000]);
+    }
+
+    #[test]
+    fn keeps_series_within_the_point_limit() {
+        let mut series = series(&[1.0, 2.0, 3.0]);
+        series.trim(0, 3);
+        assert_eq!(series.samples().len(), 3);
+        assert!(series.samples().iter().all(|s| s.count == 1));
+    }
+
+    #[test]
+    fn merges_the_older_half_in_pairs() {
+        let mut series = series(&[1.0, 5.0, 2.0, 4.0, 7.0, 8.0, 9.0, 10.0]);
+        series.trim(0, 6);
+        let samples = series.samples();
+        // Four older samples merge into two, the newer four are kept as they are
+        assert_eq!(samples.len(), 6);
+        assert_eq!(
+            samples[0],
+            Sample {
+                time: 0,
+                min: 1.0,
+                mean: 3.0,
+                max: 5.0,
+                count: 2,
+            }
+        );
+        assert_eq!(
+            samples[1],
+            Sample {
+                time: 2000,
+                min: 2.0,
+                mean: 3.0,
+                max: 4.0,
+                count: 2,
+            }
+        );
+        assert!(samples.iter().skip(2).all(|s| s.count == 1));
+        assert_eq!(samples[2].time, 4000);
+    }
+
+    #[test]
+    fn weights_merged_means_by_count() {
+        let mut series = series(&[1.0, 3.0, 10.0, 0.0, 0.0, 0.0]);
+        series.trim(0, 5);
+        // [1, 3] and [10] are merged, then those two again
+        series.trim(0, 2);
+        let first = series.samples()[0];
+        assert_eq!(first.count, 3);
+        assert_eq!(first.min, 1.0);
+        assert_eq!(first.max, 10.0);
+        assert!((first.mean - 14.0 / 3.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,25 @@
+use egui::{Color32, Pos2, Response, Sense, Stroke, Ui, Vec2};
+
+/// Draw a small mirrored peak envelope. Each value in `peaks` is one column, 0 is silence and
+/// 255 full scale.
+pub fn waveform_thumbnail(ui: &mut Ui, peaks: &[u8], size: Vec2, color: Color32) -> Response {
+    let (resp, p) = ui.allocate_painter(size, Sense::hover());
+    if peaks.is_empty() {
+        return resp;
+    }
+    let rect = resp.rect;
+    let column_width = rect.width() / peaks.len() as f32;
+    let stroke = Stroke::new(column_width.max(1.0) * 0.8, color);
+    for (i, peak) in peaks.iter().enumerate() {
+        let x = rect.left() + column_width * (i as f32 + 0.5);
+        let half_height = (*peak as f32 / 255.0 * rect.height() * 0.5).max(0.5);
+        p.line_segment(
+            [
+                Pos2::new(x, rect.center().y - half_height),
+                Pos2::new(x, rect.center().y + half_height),
+            ],
+            stroke,
+        );
+    }
+    resp
+}
@@ -0,0 +1,237 @@
+use egui::{Grid, RichText};
+
+use crate::{
+    app::ClicksMonitorApp,
+    watchdog::{self, AlarmEventKind, AlarmRule, Comparison, Metric, SNOOZE_MINUTES, Severity},
+    widget::plot::format_time,
+};
+
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Active alarms").heading());
+            if ui.button("Acknowledge all").clicked() {
+                watchdog::acknowledge_all(app);
+            }
+        });
+        active_list(app, ui);
+
+        ui.separator();
+        ui.label(RichText::new("Rules").heading());
+        rules(app, ui);
+
+        ui.separator();
+        ui.label(RichText::new("Log").heading());
+        log(app, ui);
+    });
+}
+
+/// Active alarms with their acknowledge and snooze controls. Also shown from the statusbar.
+pub fn active_list(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let active: Vec<usize> = watchdog::alarms(app)
+        .filter(|(_, _, alarm)| alarm.active || alarm.snoozed())
+        .map(|(idx, _, _)| idx)
+        .collect();
+    if active.is_empty() {
+        ui.colored_label(app.theme.active_prim, "No active alarms");
+        return;
+    }
+    Grid::new("active-alarms").num_columns(5).show(ui, |ui| {
+        for idx in active {
+            let rule = app.local_memory.watchdog.rules[idx].clone();
+            let alarm = app.watchdog.alarms[idx];
+            ui.colored_label(
+                if alarm.needs_attention() {
+                    rule.severity.color(&app.theme)
+                } else {
+                    app.theme.neutral_prim
+                },
+                RichText::new(rule.severity.name()).strong(),
+            );
+            ui.label(rule.describe());
+            ui.label(
+                RichText::new(match alarm.value {
+                    Some(value) => format!("{:.1}{}", value, rule.metric.unit()),
+                    None => "-".to_string(),
+                })
+                .monospace(),
+            );
+            ui.label(match (alarm.active, alarm.since) {
+                (true, Some(since)) => format!("for {} s", since.elapsed().as_secs()),
+                _ => "cleared".to_string(),
+            });
+            ui.horizontal(|ui| {
+                if alarm.active
+                    && ui
+                        .add_enabled(!alarm.acknowledged, egui::Button::new("Acknowledge"))
+                        .clicked()
+                {
+                    watchdog::acknowledge(app, idx);
+                }
+                if alarm.snoozed() {
+                    if ui.button("Unsnooze").clicked() {
+                        app.watchdog.alarms[idx].snoozed_until = None;
+                    }
+                } else {
+                    ui.menu_button("Snooze", |ui| {
+                        for minutes in SNOOZE_MINUTES {
+                            if ui.button(format!("{} min", minutes)).clicked() {
+                                watchdog::snooze(app, idx, minutes);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+            });
+            ui.end_row();
+        }
+    });
+}
+
+fn rules(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.vertical(|ui| {
+        if !app.local_memory.security.allow_interaction {
+            ui.disable();
+        }
+        ui.checkbox(
+            &mut app.local_memory.watchdog.popup_critical,
+            "Show a window for unacknowledged critical alarms",
+        );
+        let mut remove = None;
+        let mut changed = vec![];
+        Grid::new("alarm-rules")
+            .num_columns(8)
+            .striped(true)
+            .show(ui, |ui| {
+                for label in [
+                    "On",
+                    "Metric",
+                    "",
+                    "Threshold",
+                    "Hysteresis",
+                    "Delay",
+                    "Severity",
+                    "",
+                ] {
+                    ui.label(RichText::new(label).strong());
+                }
+                ui.end_row();
+
+                for (idx, rule) in app.local_memory.watchdog.rules.iter_mut().enumerate() {
+                    let before = rule.clone();
+                    rule_row(ui, idx, rule);
+                    if rule.metric != before.metric || rule.comparison != before.comparison {
+                        changed.push((idx, before));
+                    }
+                    if ui.button("Remove").clicked() {
+                        remove = Some(idx);
+                    }
+                    ui.end_row();
+                }
+            });
+        for (idx, before) in changed {
+            watchdog::reset_alarm(app, idx, &before);
+        }
+        if let Some(idx) = remove {
+            watchdog::remove_rule(app, idx);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add rule").clicked() {
+                app.local_memory.watchdog.rules.push(AlarmRule::new(
+                    Metric::CpuUseAudio,
+                    Comparison::Above,
+                    90.0,
+                    5.0,
+                    Severity::Warning,
+                ));
+            }
+            if ui.button("Reset to defaults").clicked() {
+                watchdog::reset_rules(app);
+            }
+        });
+    });
+}
+
+fn rule_row(ui: &mut egui::Ui, idx: usize, rule: &mut AlarmRule) {
+    ui.checkbox(&mut rule.enabled, "");
+    egui::ComboBox::from_id_salt(("alarm-metric", idx))
+        .selected_text(rule.metric.name())
+        .width(240.0)
+        .show_ui(ui, |ui| {
+            for metric in Metric::ALL {
+                ui.selectable_value(&mut rule.metric, metric, metric.name());
+            }
+        });
+    if rule.metric.is_flag() {
+        // Flags are raised at 1 and cleared at 0
+        rule.comparison = Comparison::Above;
+        rule.threshold = 0.5;
+        rule.hysteresis = 0.0;
+        ui.label("");
+        ui.label("");
+        ui.label("");
+    } else {
+        egui::ComboBox::from_id_salt(("alarm-comparison", idx))
+            .selected_text(rule.comparison.name())
+            .width(72.0)
+            .show_ui(ui, |ui| {
+                for comparison in [Comparison::Above, Comparison::Below] {
+                    ui.selectable_value(&mut rule.comparison, comparison, comparison.name());
+                }
+            });
+        ui.add(
+            egui::DragValue::new(&mut rule.threshold)
+                .speed(0.1)
+                .suffix(rule.metric.unit()),
+        );
+        ui.add(
+            egui::DragValue::new(&mut rule.hysteresis)
+                .speed(0.1)
+                .range(0.0..=f32::MAX)
+                .suffix(rule.metric.unit()),
+        );
+    }
+    ui.add(
+        egui::DragValue::new(&mut rule.delay_s)
+            .speed(0.1)
+            .range(0.0..=600.0)
+            .suffix(" s"),
+    )
+    .on_hover_text("How long the threshold must be crossed before the alarm is raised");
+    egui::ComboBox::from_id_salt(("alarm-severity", idx))
+        .selected_text(rule.severity.name())
+        .width(88.0)
+        .show_ui(ui, |ui| {
+            for severity in Severity::ALL {
+                ui.selectable_value(&mut rule.severity, severity, severity.name());
+            }
+        });
+}
+
+fn log(app: &ClicksMonitorApp, ui: &mut egui::Ui) {
+    if app.watchdog.log.is_empty() {
+        ui.label("Nothing has happened yet");
+        return;
+    }
+    Grid::new("alarm-log")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            for event in app.watchdog.log.iter().rev() {
+                ui.label(RichText::new(format_time(event.time)).monospace());
+                ui.colored_label(event.severity.color(&app.theme), event.severity.name());
+                ui.label(match event.kind {
+                    AlarmEventKind::Raised => "Raised".to_string(),
+                    AlarmEventKind::Cleared => "Cleared".to_string(),
+                    AlarmEventKind::Acknowledged => "Acknowledged".to_string(),
+                    AlarmEventKind::Snoozed(minutes) => format!("Snoozed {} min", minutes),
+                    AlarmEventKind::Discarded(count) => format!("Discarded {} messages", count),
+                });
+                ui.label(match event.value {
+                    Some(value) => format!("{} at {:.1}", event.rule, value),
+                    None => event.rule.clone(),
+                });
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,122 @@
+use crate::{
+    app::ClicksMonitorApp,
+    click::{self, ClickBackend},
+};
+use egui::{Key, RichText};
+
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.vertical(|ui| {
+        if !app.local_memory.security.allow_interaction {
+            ui.disable();
+        }
+        ui.label(RichText::new("Local Click").heading());
+        ui.label(
+            "A backup click generated by the monitor from the beat data, played through this \
+             computer's sound card.",
+        );
+        ui.add_space(8.0);
+
+        let settings = &mut app.local_memory.click;
+        let mut volume_changed = false;
+        egui::Grid::new("local-click-settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Enabled");
+                ui.checkbox(&mut settings.enabled, "");
+                ui.end_row();
+
+                ui.label("Output");
+                ui.horizontal(|ui| {
+                    for backend in [
+                        ClickBackend::SoundCard,
+                        ClickBackend::File,
+                        ClickBackend::Null,
+                    ] {
+                        ui.selectable_value(&mut settings.backend, backend, backend.name());
+                    }
+                });
+                ui.end_row();
+
+                if settings.backend == ClickBackend::File {
+                    ui.label("File");
+                    ui.text_edit_singleline(&mut settings.file_path);
+                    ui.end_row();
+                }
+
+                ui.label("Volume");
+                volume_changed = ui
+                    .add(egui::Slider::new(&mut settings.volume_db, -48.0..=0.0).suffix(" dB"))
+                    .changed();
+                ui.end_row();
+
+                ui.label("Accent downbeats");
+                ui.checkbox(&mut settings.accent, "");
+                ui.end_row();
+
+                ui.label("Latency offset");
+                ui.add(
+                    egui::Slider::new(&mut settings.latency_offset_ms, -250.0..=250.0)
+                        .suffix(" ms"),
+                )
+                .on_hover_text("Positive values play the click earlier");
+                ui.end_row();
+            });
+        if volume_changed && let Some(output) = &app.click.output {
+            output.set_volume(app.local_memory.click.volume_db);
+        }
+
+        ui.add_space(8.0);
+        match (&app.click.output, &app.click.error) {
+            (_, Some(error)) => {
+                ui.colored_label(app.theme.err_prim, error);
+                if ui.button("Retry").clicked() {
+                    app.click.error = None;
+                }
+            }
+            (Some(output), None) => {
+                ui.colored_label(
+                    app.theme.active_prim,
+                    format!("{}, {} clicks played", output.description, output.played()),
+                );
+            }
+            (None, None) => {
+                ui.colored_label(app.theme.neutral_prim, "Off");
+            }
+        }
+
+        ui.separator();
+        ui.label(RichText::new("Calibration").strong());
+        ui.label(
+            "Tap along with the local click while the transport runs. The average tap offset \
+             is how much later the click is heard than scheduled.",
+        );
+        ui.horizontal(|ui| {
+            let tapped = ui.button("Tap (space)").clicked()
+                || (!ui.ctx().wants_keyboard_input() && ui.input(|i| i.key_pressed(Key::Space)));
+            if tapped && app.click.output.is_some() {
+                click::tap(app);
+            }
+            if ui.button("Reset").clicked() {
+                app.click.taps.clear();
+            }
+        });
+        match click::calibration(app) {
+            Some(ms) => {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Heard {:.0} ms late over {} taps",
+                        ms,
+                        app.click.taps.len()
+                    ));
+                    if ui.button("Apply").clicked() {
+                        app.local_memory.click.latency_offset_ms += ms;
+                        app.click.taps.clear();
+                    }
+                });
+            }
+            None => {
+                ui.label(format!("{} taps", app.click.taps.len()));
+            }
+        }
+    });
+}
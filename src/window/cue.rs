@@ -3,9 +3,9 @@ use egui::{vec2, Align2, CornerRadius, Rect, RichText, Stroke};
 use crate::{app::ClicksMonitorApp, theme::Theme};
 use common::{
     beat::Beat,
+    cue::Cue,
     event::{Event, EventCursor, EventDescription},
     local::status::CombinedStatus,
-    protocol::request::{ControlAction, Request},
 };
 
 pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
@@ -22,6 +22,10 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
 
     // Render details in side panel for hovered beat.
     side_panel.show(ui.ctx(), |ui| {
+        crate::window::locate::mark_list(app, ui);
+        ui.separator();
+        crate::rehearsal::loop_controls(app, ui);
+        ui.separator();
         if hovered_idx == usize::MAX {
             ui.label("Hover a beat to view details.");
             return;
@@ -59,6 +63,19 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     });
 }
 
+/// Beat index and label of every rehearsal mark in a cue.
+pub fn rehearsal_marks(cue: &Cue) -> Vec<(u16, String)> {
+    cue.events
+        .iter()
+        .filter_map(|event| match event.event {
+            Some(EventDescription::RehearsalMarkEvent { label }) if !label.is_empty() => {
+                Some((event.location, label.str().to_string()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn render_cue(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) -> usize {
     let mut hovered_idx = usize::MAX;
     ui.vertical(|ui| {
@@ -76,8 +93,10 @@ fn render_cue(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) -> usize {
                     ui.available_width(),
                     app.status.cue.cue.get_beats().len() as f32 / max_beats_per_line * size * 3.0,
                 ),
-                egui::Sense::click(),
+                egui::Sense::click_and_drag(),
             );
+            let selection = app.rehearsal.selection;
+            let loop_region = app.rehearsal.loop_region.map(|r| (r.start, r.end));
             let mut cursor = EventCursor::new(&app.status.cue.cue.events);
             for (i, beat) in app.status.cue.cue.get_beats().iter().enumerate() {
                 let mut line_break_flag = false;
@@ -143,14 +162,42 @@ fn render_cue(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) -> usize {
                     beat_rect,
                     &app.status,
                 );
+
+                // Loop region and drag selection on top
+                for (range, color) in [
+                    (loop_region, app.theme.warn_prim),
+                    (selection, app.theme.cued_prim),
+                ] {
+                    if let Some((start, end)) = range
+                        && (start as usize..=end as usize).contains(&i)
+                    {
+                        ui.painter()
+                            .rect_filled(beat_rect, 0.0, color.gamma_multiply(0.25));
+                    }
+                }
+            }
+            if resp.drag_started() && hovered_idx < usize::MAX / 2 {
+                app.rehearsal.drag_anchor = Some(hovered_idx as u16);
+            }
+            if resp.dragged()
+                && hovered_idx < usize::MAX / 2
+                && let Some(anchor) = app.rehearsal.drag_anchor
+            {
+                let hovered = hovered_idx as u16;
+                app.rehearsal.selection = Some((anchor.min(hovered), anchor.max(hovered)));
+            }
+            if resp.drag_stopped() {
+                app.rehearsal.drag_anchor = None;
             }
             if app.local_memory.security.allow_interaction && resp.clicked() && hovered_idx < usize::MAX / 2 {
-                app.udp_client
-                    .send_msg(Request::ControlAction(if app.status.transport.running {
-                        ControlAction::TransportSeekBeat(hovered_idx as u16)
-                    } else {
-                        ControlAction::TransportJumpBeat(hovered_idx as u16)
-                    }));
+                crate::window::locate::locate_beat(app, hovered_idx as u16);
+            }
+            if app.local_memory.security.allow_interaction
+                && resp.double_clicked()
+                && hovered_idx < usize::MAX / 2
+                && !app.status.transport.running
+            {
+                crate::window::transport::start(app, hovered_idx as u16);
             }
         });
     });
@@ -1,7 +1,50 @@
-use egui::{vec2, Align2, CornerRadius, Rect, Stroke};
+use std::collections::HashSet;
 
-use crate::app::ClicksMonitorApp;
-use common::protocol::request::Request;
+use egui::{
+    Align2, ComboBox, CornerRadius, FontId, Rect, RichText, Sense, Stroke, epaint::TextShape, pos2,
+    vec2,
+};
+
+use crate::{
+    app::ClicksMonitorApp,
+    history::{self, MixerChange},
+    widget::meter::vertical_meter,
+};
+
+const MSG_NO_INTERACTION: &str =
+    "Routing is disabled when client is locked. Unlock client to change routing.";
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub struct JackWindowMemory {
+    pub output_names: Vec<String>,
+    pub presets: Vec<RoutingPreset>,
+    #[serde(skip)]
+    paint: Option<RoutingPaint>,
+    #[serde(skip)]
+    tools: RoutingTools,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct RoutingPreset {
+    pub name: String,
+    pub connections: Vec<u32>,
+}
+
+/// An ongoing drag across the matrix. Every crosspoint the pointer passes is set to `connect`,
+/// which is decided by the first crosspoint of the drag.
+struct RoutingPaint {
+    connect: bool,
+    visited: HashSet<(usize, usize)>,
+}
+
+#[derive(Default)]
+struct RoutingTools {
+    output: usize,
+    copy_to: usize,
+    pair_input: usize,
+    pair_output: usize,
+    preset_name: String,
+}
 
 pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     ui.horizontal_top(|ui| {
@@ -9,8 +52,12 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
         ui.separator();
         ui.vertical(|ui| {
             ui.label(egui::RichText::new("Routing Matrix").heading());
-            render_routing_matrix(app, ui);
+            egui::ScrollArea::both().show(ui, |ui| {
+                render_routing_matrix(app, ui);
+            });
         });
+        ui.separator();
+        render_tools(app, ui);
     });
 }
 
@@ -58,91 +105,383 @@ pub fn render_details(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     });
 }
 
-pub fn render_routing_matrix(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
-    let size = 25.0;
-    let mut hovered_connection = (0_usize, 0_usize);
+/// Number of (inputs, outputs) in the matrix, limited to what the connection bitmasks can hold.
+fn matrix_size(app: &ClicksMonitorApp) -> (usize, usize) {
     let io = app.status.jack_status.io_size;
+    (
+        io.0.min(app.status.jack_status.connections.len()),
+        io.1.min(u32::BITS as usize),
+    )
+}
+
+fn is_connected(app: &ClicksMonitorApp, from: usize, to: usize) -> bool {
+    app.status.jack_status.connections[from] & (0x01 << to) > 0
+}
+
+/// Crosspoint state including changes staged in arm mode.
+fn is_connected_staged(app: &ClicksMonitorApp, from: usize, to: usize) -> bool {
+    app.local_memory
+        .history
+        .staged_routing(from, to)
+        .unwrap_or_else(|| is_connected(app, from, to))
+}
+
+/// Request a crosspoint change, if it differs from the current routing.
+pub fn set_connection(app: &mut ClicksMonitorApp, from: usize, to: usize, connect: bool) {
+    if is_connected_staged(app, from, to) != connect {
+        history::submit(
+            app,
+            MixerChange::Routing {
+                from: from as u8,
+                to: to as u8,
+                connect,
+            },
+        );
+    }
+}
+
+/// Request every crosspoint change needed to make the routing match `connections`.
+pub fn apply_connections(app: &mut ClicksMonitorApp, connections: &[u32]) {
+    let io = matrix_size(app);
+    app.local_memory.history.begin_group();
+    for from in 0..io.0 {
+        let target = connections.get(from).copied().unwrap_or(0);
+        for to in 0..io.1 {
+            set_connection(app, from, to, target & (0x01 << to) > 0);
+        }
+    }
+    app.local_memory.history.end_group();
+}
+
+pub fn input_name(app: &ClicksMonitorApp, idx: usize) -> String {
+    match app.system_config.channels.get(idx) {
+        Some(conf) if !conf.name.is_empty() => conf.name.str().to_string(),
+        _ => format!("In {}", idx + 1),
+    }
+}
+
+pub fn output_name(app: &ClicksMonitorApp, idx: usize) -> String {
+    match app.local_memory.jack.output_names.get(idx) {
+        Some(name) if !name.is_empty() => name.clone(),
+        _ => format!("Out {}", idx + 1),
+    }
+}
+
+pub fn render_routing_matrix(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    const SIZE: f32 = 25.0;
+    const LABEL_WIDTH: f32 = 160.0;
+    const HEADER_HEIGHT: f32 = 120.0;
+    let io = matrix_size(app);
+    let font = FontId::monospace(SIZE * 0.5);
 
     let (rect, resp) = ui.allocate_exact_size(
-        egui::Vec2 {
-            x: size * (io.1 + 1) as f32,
-            y: size * (io.0 + 1) as f32,
-        },
-        egui::Sense::click(),
+        vec2(
+            LABEL_WIDTH + SIZE * io.1 as f32,
+            HEADER_HEIGHT + SIZE * io.0 as f32,
+        ),
+        Sense::click_and_drag(),
     );
+    let grid_origin = rect.min + vec2(LABEL_WIDTH, HEADER_HEIGHT);
+
+    let hovered = ui.input(|i| i.pointer.hover_pos()).and_then(|pos| {
+        let rel = (pos - grid_origin) / SIZE;
+        if rel.x >= 0.0 && rel.y >= 0.0 && (rel.y as usize) < io.0 && (rel.x as usize) < io.1 {
+            Some((rel.y as usize, rel.x as usize))
+        } else {
+            None
+        }
+    });
+
+    // Input names along the left edge
+    for from in 0..io.0 {
+        ui.painter().text(
+            pos2(
+                grid_origin.x - SIZE * 0.25,
+                grid_origin.y + SIZE * (from as f32 + 0.5),
+            ),
+            Align2::RIGHT_CENTER,
+            format!("{} {:>2}", input_name(app, from), from + 1),
+            font.clone(),
+            if hovered.is_some_and(|h| h.0 == from) {
+                app.theme.cued_prim
+            } else {
+                app.theme.neutral_prim
+            },
+        );
+    }
+
+    // Output names along the top edge, rotated to read bottom to top
+    for to in 0..io.1 {
+        let galley = ui.painter().layout_no_wrap(
+            format!("{:>2} {}", to + 1, output_name(app, to)),
+            font.clone(),
+            if hovered.is_some_and(|h| h.1 == to) {
+                app.theme.cued_prim
+            } else {
+                app.theme.neutral_prim
+            },
+        );
+        let pos = pos2(
+            grid_origin.x + SIZE * (to as f32 + 0.5) - galley.size().y * 0.5,
+            grid_origin.y - SIZE * 0.25,
+        );
+        ui.painter().add(
+            TextShape::new(pos, galley, app.theme.neutral_prim)
+                .with_angle(-std::f32::consts::FRAC_PI_2),
+        );
+    }
+
+    for from in 0..io.0 {
+        for to in 0..io.1 {
+            let tile_rect = Rect::from_min_size(
+                grid_origin + vec2(to as f32, from as f32) * SIZE,
+                vec2(SIZE, SIZE),
+            );
+            render_routing_tile(app, ui, hovered == Some((from, to)), tile_rect, (from, to));
+        }
+    }
+
+    // Hardware output meters, one under each output column
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        ui.add_space(LABEL_WIDTH);
+        for output in 0..io.1.min(app.output_meters.len()) {
+            ui.add_space(2.0);
+            vertical_meter(
+                ui,
+                &mut app.output_meters[output],
+                &app.theme,
+                vec2(SIZE - 4.0, SIZE * 4.0),
+            );
+            ui.add_space(2.0);
+        }
+    });
+
+    if !app.local_memory.security.allow_interaction {
+        resp.on_hover_text(MSG_NO_INTERACTION);
+        // Close the group of a drag that was cut short by the lock
+        if app.local_memory.jack.paint.take().is_some() {
+            app.local_memory.history.end_group();
+        }
+        return;
+    }
 
-    let half_center = egui::vec2(size, size) * 0.5;
-
-    // loop one index extra, because we use the 0 column and 0 row for displaying numbers, and 1-32
-    // and 1-X respective for actual grid
-    for from in 0..33 {
-        for to in 0..(io.1 + 1) {
-            let x: f32 = to as f32 * size;
-            let y: f32 = from as f32 * size;
-            let tile_origin = rect.min + egui::vec2(x, y);
-            let rect = Rect {
-                min: tile_origin,
-                max: tile_origin + 2.0 * half_center,
-            };
-
-            if from == 0 && to == 0 {
-                continue;
+    // Pressing a crosspoint starts painting with the toggled value of that crosspoint, and
+    // dragging applies the same value to every crosspoint passed until the button is released.
+    if resp.is_pointer_button_down_on() {
+        if let Some((from, to)) = hovered {
+            if app.local_memory.jack.paint.is_none() {
+                app.local_memory.history.begin_group();
             }
-            if (from == 0) != (to == 0) {
-                ui.painter().text(
-                    tile_origin + half_center,
-                    Align2::CENTER_CENTER,
-                    from.max(to).to_string(),
-                    egui::FontId {
-                        size: size * 0.5,
-                        family: egui::FontFamily::Monospace,
-                    },
-                    app.theme.neutral_prim,
-                );
-                continue;
+            let connect = !is_connected_staged(app, from, to);
+            let paint = app.local_memory.jack.paint.get_or_insert(RoutingPaint {
+                connect,
+                visited: HashSet::new(),
+            });
+            let connect = paint.connect;
+            if paint.visited.insert((from, to)) {
+                set_connection(app, from, to, connect);
             }
+        }
+    } else if app.local_memory.jack.paint.take().is_some() {
+        app.local_memory.history.end_group();
+    }
+}
 
-            let mut hovered = false;
-            if ui.rect_contains_pointer(rect) {
-                hovered_connection = (from - 1, to - 1);
-                hovered = true;
-            }
-            render_routing_tile(app, ui, hovered, rect, (from - 1, to - 1));
-
-            if hovered {
-                ui.painter().text(
-                    tile_origin + half_center - vec2(0.0, size),
-                    Align2::CENTER_CENTER,
-                    to,
-                    egui::FontId {
-                        size: size * 0.5,
-                        family: egui::FontFamily::Monospace,
-                    },
-                    app.theme.neutral_prim,
-                );
-                ui.painter().text(
-                    tile_origin + half_center - vec2(size, 0.0),
-                    Align2::CENTER_CENTER,
-                    from,
-                    egui::FontId {
-                        size: size * 0.5,
-                        family: egui::FontFamily::Monospace,
-                    },
-                    app.theme.neutral_prim,
+pub fn render_tools(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.vertical(|ui| {
+        if !app.local_memory.security.allow_interaction {
+            ui.disable();
+        }
+        let io = matrix_size(app);
+        if io.0 == 0 || io.1 == 0 {
+            return;
+        }
+        let output_names: Vec<String> = (0..io.1).map(|i| output_name(app, i)).collect();
+        let input_names: Vec<String> = (0..io.0).map(|i| input_name(app, i)).collect();
+        let tools = &mut app.local_memory.jack.tools;
+        tools.output = tools.output.min(io.1 - 1);
+        tools.copy_to = tools.copy_to.min(io.1 - 1);
+        tools.pair_input = tools.pair_input.min(io.0.saturating_sub(2));
+        tools.pair_output = tools.pair_output.min(io.1.saturating_sub(2));
+
+        ui.label(RichText::new("Routing tools").heading());
+        let (clear, copy, map_pair) = egui::Grid::new("routing-tools")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Output");
+                output_selector(ui, "routing-tools-output", &mut tools.output, &output_names);
+                ui.end_row();
+
+                ui.label("");
+                let clear = ui.button("Clear output").clicked();
+                ui.end_row();
+
+                ui.label("Copy to");
+                output_selector(
+                    ui,
+                    "routing-tools-copy-to",
+                    &mut tools.copy_to,
+                    &output_names,
                 );
+                ui.end_row();
+
+                ui.label("");
+                let copy = ui.button("Copy column").clicked();
+                ui.end_row();
+
+                ui.label("Stereo inputs");
+                ComboBox::new("routing-tools-pair-input", "")
+                    .selected_text(pair_label(&input_names, tools.pair_input))
+                    .show_ui(ui, |ui| {
+                        for i in 0..io.0.saturating_sub(1) {
+                            ui.selectable_value(
+                                &mut tools.pair_input,
+                                i,
+                                pair_label(&input_names, i),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Stereo outputs");
+                ComboBox::new("routing-tools-pair-output", "")
+                    .selected_text(pair_label(&output_names, tools.pair_output))
+                    .show_ui(ui, |ui| {
+                        for i in 0..io.1.saturating_sub(1) {
+                            ui.selectable_value(
+                                &mut tools.pair_output,
+                                i,
+                                pair_label(&output_names, i),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("");
+                let map_pair = io.0 > 1 && io.1 > 1 && ui.button("Map stereo pair").clicked();
+                ui.end_row();
+
+                (clear, copy, map_pair)
+            })
+            .inner;
+
+        let tools = &app.local_memory.jack.tools;
+        let (output, copy_to, pair_input, pair_output) = (
+            tools.output,
+            tools.copy_to,
+            tools.pair_input,
+            tools.pair_output,
+        );
+        // Only group when a tool fired, so a drag paint group in progress is left open
+        let grouped = clear || copy || map_pair;
+        if grouped {
+            app.local_memory.history.begin_group();
+        }
+        if clear {
+            for from in 0..io.0 {
+                set_connection(app, from, output, false);
             }
         }
-    }
+        if copy {
+            for from in 0..io.0 {
+                let connect = is_connected_staged(app, from, output);
+                set_connection(app, from, copy_to, connect);
+            }
+        }
+        if map_pair {
+            set_connection(app, pair_input, pair_output, true);
+            set_connection(app, pair_input + 1, pair_output + 1, true);
+        }
+        if grouped {
+            app.local_memory.history.end_group();
+        }
 
-    if resp.clicked() {
-        app.udp_client.send_msg(Request::ChangeRouting(
-            hovered_connection.0 as u8,
-            hovered_connection.1 as u8,
-            (app.status.jack_status.connections[hovered_connection.0]
-                & (0x01 << hovered_connection.1))
-                == 0,
-        ))
-    }
+        ui.separator();
+        ui.label(RichText::new("Changes").heading());
+        history::controls(app, ui);
+
+        ui.separator();
+        ui.label(RichText::new("Presets").heading());
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut app.local_memory.jack.tools.preset_name);
+            let name = app.local_memory.jack.tools.preset_name.trim().to_string();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save current"))
+                .clicked()
+            {
+                let preset = RoutingPreset {
+                    name: name.clone(),
+                    connections: app.status.jack_status.connections.to_vec(),
+                };
+                let presets = &mut app.local_memory.jack.presets;
+                match presets.iter_mut().find(|p| p.name == name) {
+                    Some(existing) => *existing = preset,
+                    None => presets.push(preset),
+                }
+                app.local_memory.jack.tools.preset_name.clear();
+            }
+        });
+        let mut apply = None;
+        let mut delete = None;
+        egui::Grid::new("routing-presets")
+            .num_columns(3)
+            .show(ui, |ui| {
+                for (i, preset) in app.local_memory.jack.presets.iter().enumerate() {
+                    ui.label(&preset.name);
+                    if ui.button("Apply").clicked() {
+                        apply = Some(preset.connections.clone());
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(connections) = apply {
+            apply_connections(app, &connections);
+        }
+        if let Some(i) = delete {
+            app.local_memory.jack.presets.remove(i);
+        }
+
+        ui.separator();
+        egui::CollapsingHeader::new("Output names").show(ui, |ui| {
+            let names = &mut app.local_memory.jack.output_names;
+            if names.len() < io.1 {
+                names.resize(io.1, String::new());
+            }
+            egui::Grid::new("routing-output-names")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (i, name) in names.iter_mut().take(io.1).enumerate() {
+                        ui.label(format!("{:>2}", i + 1));
+                        ui.text_edit_singleline(name);
+                        ui.end_row();
+                    }
+                });
+        });
+    });
+}
+
+fn output_selector(ui: &mut egui::Ui, id: &str, selected: &mut usize, names: &[String]) {
+    ComboBox::new(id, "")
+        .selected_text(format!("{:>2} {}", *selected + 1, names[*selected]))
+        .show_ui(ui, |ui| {
+            for (i, name) in names.iter().enumerate() {
+                ui.selectable_value(selected, i, format!("{:>2} {}", i + 1, name));
+            }
+        });
+}
+
+fn pair_label(names: &[String], idx: usize) -> String {
+    format!(
+        "{}+{} ({} / {})",
+        idx + 1,
+        idx + 2,
+        names.get(idx).map_or("", |n| n.as_str()),
+        names.get(idx + 1).map_or("", |n| n.as_str())
+    )
 }
 
 fn render_routing_tile(
@@ -163,7 +502,17 @@ fn render_routing_tile(
         CornerRadius::ZERO,
         if hovered {
             app.theme.cued_prim
-        } else if (app.status.jack_status.connections[connection.0] & (0x01 << connection.1)) > 0 {
+        } else if let Some(connect) = app
+            .local_memory
+            .history
+            .staged_routing(connection.0, connection.1)
+        {
+            if connect {
+                app.theme.warn_prim
+            } else {
+                app.theme.err_prim_wk
+            }
+        } else if is_connected(app, connection.0, connection.1) {
             app.theme.active_prim
         } else {
             app.theme.base_wk
@@ -0,0 +1,384 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    app::ClicksMonitorApp,
+    window::{WindowTab, cue::rehearsal_marks},
+};
+use common::{
+    beat::Beat,
+    protocol::request::{ControlAction, Request},
+};
+use egui::{Key, KeyboardShortcut, Modifiers, RichText};
+
+pub const MARK_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::M);
+pub const BAR_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::B);
+const MARK_ENTRY: &str = "Jump to mark";
+const BAR_ENTRY: &str = "Jump to bar.beat";
+/// How long a locate waits for its cue to load before it is given up.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Bar lookup input and the occurrences to choose between when a bar number repeats.
+#[derive(Default)]
+pub struct BarLocator {
+    pub input: String,
+    pub candidates: Vec<u16>,
+    pub error: Option<String>,
+    /// Cue, beat and request time of a locate waiting for its cue to load.
+    pub pending: Option<(u8, u16, Instant)>,
+}
+
+/// A parsed "bar.beat" or "bar" position, optionally with "#n" to pick the nth occurrence of
+/// a repeated bar number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BarPosition {
+    pub bar: u16,
+    pub count: Option<u8>,
+    pub occurrence: Option<usize>,
+}
+
+pub fn parse_bar_beat(text: &str) -> Option<BarPosition> {
+    let (position, occurrence) = match text.trim().split_once('#') {
+        Some((position, n)) => (position, Some(n.trim().parse::<usize>().ok()?.max(1))),
+        None => (text.trim(), None),
+    };
+    let (bar, count) = match position.split_once('.') {
+        Some((bar, count)) => (bar, Some(count.parse().ok()?)),
+        None => (position, None),
+    };
+    Some(BarPosition {
+        bar: bar.parse().ok()?,
+        count,
+        occurrence,
+    })
+}
+
+/// Every beat index in a cue's beats at the position. Without a beat count this is the first
+/// beat of each occurrence of the bar, where a count that doesn't go up starts a new one.
+pub fn find_bar_beat(beats: &[Beat], position: BarPosition) -> Vec<u16> {
+    beats
+        .iter()
+        .enumerate()
+        .filter(|(i, beat)| {
+            beat.bar_number == position.bar
+                && match position.count {
+                    Some(count) => beat.count == count,
+                    None => {
+                        *i == 0
+                            || beats[i - 1].bar_number != position.bar
+                            || beats[i - 1].count >= beat.count
+                    }
+                }
+        })
+        .map(|(i, _)| i as u16)
+        .collect()
+}
+
+/// Go to a bar.beat position, or leave the occurrences as candidates when it is ambiguous.
+pub fn locate_bar_beat(app: &mut ClicksMonitorApp, text: &str) {
+    let locator = &mut app.bar_locator;
+    locator.candidates.clear();
+    locator.error = None;
+    let Some(position) = parse_bar_beat(text) else {
+        locator.error = Some(format!("\"{}\" is not a bar or bar.beat", text.trim()));
+        return;
+    };
+    let found = find_bar_beat(&app.status.cue.cue.beats, position);
+    let beat = match (found.len(), position.occurrence) {
+        (0, _) => None,
+        (_, Some(n)) => found.get(n - 1).copied(),
+        (1, None) => found.first().copied(),
+        (_, None) => {
+            app.bar_locator.candidates = found;
+            return;
+        }
+    };
+    match beat {
+        Some(beat) => locate_beat(app, beat),
+        None => app.bar_locator.error = Some(format!("Bar {} not found", text.trim())),
+    }
+}
+
+/// The rehearsal mark at or before a beat, to tell repeated bars apart.
+fn mark_before(app: &ClicksMonitorApp, beat_idx: u16) -> Option<String> {
+    rehearsal_marks(&app.status.cue.cue)
+        .into_iter()
+        .take_while(|(beat, _)| *beat <= beat_idx)
+        .last()
+        .map(|(_, label)| label)
+}
+
+/// Move the transport to a beat in the current cue. Seeks while running so the music keeps
+/// going, and jumps while stopped.
+pub fn locate_beat(app: &mut ClicksMonitorApp, beat_idx: u16) {
+    app.udp_client
+        .send_msg(Request::ControlAction(if app.status.transport.running {
+            ControlAction::TransportSeekBeat(beat_idx)
+        } else {
+            ControlAction::TransportJumpBeat(beat_idx)
+        }));
+}
+
+/// Locate to a beat in any cue of the show. Another cue is loaded first, and the locate is
+/// sent once its data arrives, so it can't land in the old cue.
+pub fn locate_cue_beat(app: &mut ClicksMonitorApp, cue_idx: u8, beat_idx: u16) {
+    if cue_idx == app.status.cue.cue_idx {
+        app.bar_locator.pending = None;
+        locate_beat(app, beat_idx);
+        return;
+    }
+    app.udp_client
+        .send_msg(Request::ControlAction(ControlAction::LoadCueByIndex(
+            cue_idx,
+        )));
+    app.bar_locator.pending = Some((cue_idx, beat_idx, Instant::now()));
+}
+
+/// Send a locate that was waiting for its cue. Called after CueData has been stored.
+pub fn on_cue_data(app: &mut ClicksMonitorApp) {
+    let Some((cue_idx, beat_idx, requested)) = app.bar_locator.pending else {
+        return;
+    };
+    if requested.elapsed() > LOAD_TIMEOUT {
+        app.bar_locator.pending = None;
+    } else if cue_idx == app.status.cue.cue_idx {
+        app.bar_locator.pending = None;
+        locate_beat(app, beat_idx);
+    }
+}
+
+/// Find a rehearsal mark by label. Exact matches win over prefix matches, and marks in the
+/// current cue win over the rest of the show. Matching ignores case.
+pub fn find_mark(app: &ClicksMonitorApp, query: &str) -> Option<(u8, u16)> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    let current = app.status.cue.cue_idx;
+    let mut marks: Vec<(u8, u16, String)> = rehearsal_marks(&app.status.cue.cue)
+        .into_iter()
+        .map(|(beat, label)| (current, beat, label.to_lowercase()))
+        .collect();
+    for (i, cue) in app.status.show.cues.iter().enumerate() {
+        if i as u8 != current {
+            marks.extend(
+                rehearsal_marks(cue)
+                    .into_iter()
+                    .map(|(beat, label)| (i as u8, beat, label.to_lowercase())),
+            );
+        }
+    }
+    marks
+        .iter()
+        .find(|(_, _, label)| *label == query)
+        .or_else(|| marks.iter().find(|(_, _, label)| label.starts_with(&query)))
+        .map(|(cue, beat, _)| (*cue, *beat))
+}
+
+/// Open the jump to mark and bar entries with their shortcuts, and act on them once submitted.
+pub fn handle_shortcuts(app: &mut ClicksMonitorApp, ctx: &egui::Context) {
+    if !app.local_memory.security.allow_interaction {
+        return;
+    }
+    if !ctx.wants_keyboard_input() && ctx.input_mut(|i| i.consume_shortcut(&MARK_SHORTCUT)) {
+        app.text_entry.open(MARK_ENTRY);
+    }
+    if !ctx.wants_keyboard_input() && ctx.input_mut(|i| i.consume_shortcut(&BAR_SHORTCUT)) {
+        app.text_entry.open(BAR_ENTRY);
+    }
+    if app.text_entry.submitted(BAR_ENTRY) {
+        locate_bar_beat(app, &app.text_entry.get_text());
+        app.text_entry.done();
+        if !app.bar_locator.candidates.is_empty() || app.bar_locator.error.is_some() {
+            app.local_memory.current_tab = WindowTab::CueTimeline;
+        }
+    }
+    if app.text_entry.submitted(MARK_ENTRY) {
+        if let Some((cue, beat)) = find_mark(app, &app.text_entry.get_text()) {
+            locate_cue_beat(app, cue, beat);
+        }
+        app.text_entry.done();
+    }
+}
+
+/// Rehearsal marks of the current cue as buttons and the bar entry, with the rest of the show
+/// below.
+pub fn mark_list(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    if !app.local_memory.security.allow_interaction {
+        ui.disable();
+    }
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Rehearsal marks").strong());
+        if ui
+            .small_button(format!(
+                "Jump to mark... ({})",
+                ui.ctx().format_shortcut(&MARK_SHORTCUT)
+            ))
+            .clicked()
+        {
+            app.text_entry.open(MARK_ENTRY);
+        }
+    });
+
+    let marks = rehearsal_marks(&app.status.cue.cue);
+    let beat_idx = app.status.beat_state().beat_idx;
+    ui.horizontal_wrapped(|ui| {
+        if marks.is_empty() {
+            ui.label("No rehearsal marks in this cue.");
+        }
+        for (i, (beat, label)) in marks.iter().enumerate() {
+            let active = *beat <= beat_idx && marks.get(i + 1).is_none_or(|next| next.0 > beat_idx);
+            let bar = app
+                .status
+                .cue
+                .cue
+                .get_beat(*beat)
+                .map_or(0, |b| b.bar_number);
+            if ui
+                .selectable_label(active, RichText::new(label).monospace())
+                .on_hover_text(format!("Bar {} (beat {})", bar, beat))
+                .clicked()
+            {
+                locate_beat(app, *beat);
+            }
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Go to bar");
+        let resp = egui::TextEdit::singleline(&mut app.bar_locator.input)
+            .hint_text("57.3")
+            .desired_width(80.0)
+            .show(ui)
+            .response
+            .on_hover_text(format!(
+                "bar.beat or bar, add #2 for the second occurrence of a repeated bar ({})",
+                ui.ctx().format_shortcut(&BAR_SHORTCUT)
+            ));
+        if resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+            let text = app.bar_locator.input.clone();
+            locate_bar_beat(app, &text);
+        }
+        if let Some(error) = &app.bar_locator.error {
+            ui.colored_label(app.theme.err_prim, error);
+        }
+    });
+    if !app.bar_locator.candidates.is_empty() {
+        ui.label("That bar occurs more than once:");
+        ui.horizontal_wrapped(|ui| {
+            for beat in app.bar_locator.candidates.clone() {
+                let text = match mark_before(app, beat) {
+                    Some(mark) => format!("After {} (beat {})", mark, beat),
+                    None => format!("Beat {}", beat),
+                };
+                if ui.button(text).clicked() {
+                    locate_beat(app, beat);
+                    app.bar_locator.candidates.clear();
+                }
+            }
+        });
+    }
+
+    egui::CollapsingHeader::new("Whole show").show(ui, |ui| {
+        let cues = app.status.show.cues.clone();
+        egui::Grid::new("show-marks-grid").show(ui, |ui| {
+            for (i, cue) in cues.iter().enumerate() {
+                let marks = rehearsal_marks(cue);
+                if marks.is_empty() {
+                    continue;
+                }
+                ui.colored_label(
+                    if i == app.status.cue.cue_idx as usize {
+                        app.theme.active_prim
+                    } else {
+                        app.theme.neutral_prim
+                    },
+                    format!(
+                        "{} {}",
+                        cue.metadata.human_ident.str(),
+                        cue.metadata.name.str()
+                    ),
+                );
+                ui.horizontal_wrapped(|ui| {
+                    for (beat, label) in marks {
+                        if ui.small_button(label).clicked() {
+                            locate_cue_beat(app, i as u8, beat);
+                        }
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beats(bars: &[(u16, u8)]) -> Vec<Beat> {
+        bars.iter()
+            .flat_map(|&(bar_number, counts)| {
+                (1..=counts).map(move |count| Beat {
+                    count,
+                    bar_number,
+                    length: 500_000,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_bar_beat_and_occurrence() {
+        let position = |bar, count, occurrence| BarPosition {
+            bar,
+            count,
+            occurrence,
+        };
+        assert_eq!(parse_bar_beat("12"), Some(position(12, None, None)));
+        assert_eq!(parse_bar_beat(" 12.3 "), Some(position(12, Some(3), None)));
+        assert_eq!(
+            parse_bar_beat("12.3#2"),
+            Some(position(12, Some(3), Some(2)))
+        );
+        assert_eq!(parse_bar_beat("12#2"), Some(position(12, None, Some(2))));
+        // The first occurrence is the lowest there is
+        assert_eq!(parse_bar_beat("12#0"), Some(position(12, None, Some(1))));
+    }
+
+    #[test]
+    fn rejects_malformed_positions() {
+        for text in [
+            "", "bar", "12.", ".3", "12.x", "12#", "12#x", "-1", "12.3.4",
+        ] {
+            assert_eq!(parse_bar_beat(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn finds_each_occurrence_of_a_repeated_bar() {
+        // Bars 1-2, then bar 2 again after a repeat, then bar 3
+        let beats = beats(&[(1, 4), (2, 4), (2, 4), (3, 3)]);
+        let bar = |bar, count| BarPosition {
+            bar,
+            count,
+            occurrence: None,
+        };
+        assert_eq!(find_bar_beat(&beats, bar(1, None)), vec![0]);
+        assert_eq!(find_bar_beat(&beats, bar(2, None)), vec![4, 8]);
+        assert_eq!(find_bar_beat(&beats, bar(2, Some(3))), vec![6, 10]);
+        assert_eq!(find_bar_beat(&beats, bar(3, Some(3))), vec![14]);
+        assert!(find_bar_beat(&beats, bar(3, Some(4))).is_empty());
+        assert!(find_bar_beat(&beats, bar(9, None)).is_empty());
+    }
+
+    #[test]
+    fn finds_repeated_bars_that_are_not_adjacent() {
+        let beats = beats(&[(1, 2), (2, 2), (1, 2)]);
+        let position = BarPosition {
+            bar: 1,
+            count: None,
+            occurrence: None,
+        };
+        assert_eq!(find_bar_beat(&beats, position), vec![0, 4]);
+    }
+}
@@ -1,19 +1,26 @@
+pub mod alarms;
 pub mod beats;
+pub mod click;
 pub mod connection;
 pub mod cue;
 pub mod events;
 pub mod jack;
 pub mod local_config;
+pub mod locate;
 pub mod logs;
 pub mod navigation;
 pub mod network;
 pub mod performance;
 pub mod playback;
+pub mod report;
 pub mod security;
 pub mod settings_audio;
+pub mod show;
+pub mod stage;
 pub mod sources;
 pub mod statusbar;
 pub mod system_config;
+pub mod tempo;
 pub mod time;
 pub mod transport;
 
@@ -23,19 +30,26 @@ pub enum WindowTab {
     #[default]
     SourcesTime,
     SourcesPlayback,
+    CueShow,
     CueTimeline,
     CueBeats,
     CueEvents,
+    CueTempo,
     ControlTransport,
     ControlRunEvent,
     ControlSystem,
+    ControlReport,
     SystemLogs,
     SystemPerformance,
+    SystemAlarms,
     SystemNetwork,
+    SystemChannels,
+    SystemRouting,
     SystemAudio,
     PreferencesAppearance,
     PreferencesHotkeys,
     PreferencesSecurity,
+    PreferencesClick,
 }
 
 impl WindowTab {
@@ -44,17 +58,26 @@ impl WindowTab {
             Self::SourcesTime | Self::SourcesOverview | Self::SourcesPlayback => {
                 WindowCategory::Sources
             }
-            Self::CueTimeline | Self::CueBeats | Self::CueEvents => WindowCategory::Cue,
-            Self::ControlSystem | Self::ControlRunEvent | Self::ControlTransport => {
-                WindowCategory::Control
-            }
+            Self::CueShow
+            | Self::CueTimeline
+            | Self::CueBeats
+            | Self::CueEvents
+            | Self::CueTempo => WindowCategory::Cue,
+            Self::ControlSystem
+            | Self::ControlRunEvent
+            | Self::ControlTransport
+            | Self::ControlReport => WindowCategory::Control,
             Self::SystemLogs
             | Self::SystemPerformance
+            | Self::SystemAlarms
             | Self::SystemNetwork
+            | Self::SystemChannels
+            | Self::SystemRouting
             | Self::SystemAudio => WindowCategory::System,
-            Self::PreferencesAppearance | Self::PreferencesHotkeys | Self::PreferencesSecurity => {
-                WindowCategory::Preferences
-            }
+            Self::PreferencesAppearance
+            | Self::PreferencesHotkeys
+            | Self::PreferencesSecurity
+            | Self::PreferencesClick => WindowCategory::Preferences,
         }
     }
 
@@ -63,19 +86,26 @@ impl WindowTab {
             Self::SourcesOverview => "Overview",
             Self::SourcesTime => "Time",
             Self::SourcesPlayback => "Playback",
+            Self::CueShow => "Show",
             Self::CueTimeline => "Timeline",
             Self::CueBeats => "Beats",
             Self::CueEvents => "Events",
+            Self::CueTempo => "Tempo",
             Self::ControlTransport => "Transport",
             Self::ControlRunEvent => "Run Event",
             Self::ControlSystem => "File System",
+            Self::ControlReport => "Show Report",
             Self::SystemLogs => "Logs",
             Self::SystemPerformance => "Performance",
+            Self::SystemAlarms => "Alarms",
             Self::SystemNetwork => "Network",
+            Self::SystemChannels => "Channels",
+            Self::SystemRouting => "Routing",
             Self::SystemAudio => "Audio",
             Self::PreferencesAppearance => "Appearance",
             Self::PreferencesHotkeys => "Hotkeys",
             Self::PreferencesSecurity => "Security",
+            Self::PreferencesClick => "Local Click",
         }
         .to_string()
     }
@@ -19,19 +19,26 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                     WindowTab::SourcesOverview,
                     WindowTab::SourcesTime,
                     WindowTab::SourcesPlayback,
+                    WindowTab::CueShow,
                     WindowTab::CueTimeline,
                     WindowTab::CueBeats,
                     WindowTab::CueEvents,
+                    WindowTab::CueTempo,
                     WindowTab::ControlTransport,
                     WindowTab::ControlRunEvent,
                     WindowTab::ControlSystem,
+                    WindowTab::ControlReport,
                     WindowTab::SystemLogs,
                     WindowTab::SystemPerformance,
+                    WindowTab::SystemAlarms,
                     WindowTab::SystemNetwork,
+                    WindowTab::SystemChannels,
+                    WindowTab::SystemRouting,
                     WindowTab::SystemAudio,
                     WindowTab::PreferencesAppearance,
                     WindowTab::PreferencesHotkeys,
                     WindowTab::PreferencesSecurity,
+                    WindowTab::PreferencesClick,
                 ] {
                     if tab.category() != cat {
                         cat = tab.category();
@@ -58,6 +58,7 @@ pub fn client_data_use(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
         for kind in [
             RequestType::ControlCommand,
             RequestType::Initialize,
+            RequestType::Deinitialize,
             RequestType::NotifySubscribers,
             RequestType::Ping,
             RequestType::RoutingChange,
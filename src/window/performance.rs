@@ -1,176 +1,580 @@
-use std::{collections::VecDeque, ops::Sub};
+use std::{collections::VecDeque, fmt::Write};
 
-use common::protocol::message::Heartbeat;
+use chrono::DateTime;
+use common::{mem::typeflags::MessageType, protocol::message::Heartbeat};
 use egui::{Align2, Color32, FontId, Pos2, Rect, RichText, Sense, Stroke, Vec2};
 
-use crate::app::ClicksMonitorApp;
+use crate::{
+    app::ClicksMonitorApp,
+    watchdog::{self, Metric},
+    widget::plot::{PlotView, Sample, TimePlot, TimeSeries},
+};
 
-#[derive(serde::Deserialize, serde::Serialize, Default)]
+const HISTOGRAM_RANGE_MS: f32 = 20.0;
+const HISTOGRAM_BINS: usize = 80;
+const TIMING_PLOT_SIZE: Vec2 = Vec2::new(740.0, 240.0);
+const PLOT_HEIGHT: f32 = 180.0;
+const SAMPLE_INTERVAL_MS: i64 = 1000;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct PerformanceWindowMemory {
-    pub heartbeats: VecDeque<Heartbeat>,
+    /// Minutes of history kept.
+    pub history_minutes: u32,
+    /// Samples kept per series before the oldest are downsampled.
+    pub max_points: usize,
+    /// Beat interval error that raises an alert.
+    pub beat_alert_ms: f32,
+    pub export_path: String,
+    /// Kept between sessions, so a show can still be exported after a restart.
+    pub history: PerformanceHistory,
+    #[serde(skip)]
+    pub view: PlotView,
+    #[serde(skip)]
+    export_result: Option<Result<String, String>>,
 }
 
-pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
-    if ui.button("Clear history").clicked() {
-        app.local_memory.performance.heartbeats.clear();
+impl Default for PerformanceWindowMemory {
+    fn default() -> Self {
+        Self {
+            history_minutes: 240,
+            max_points: 4000,
+            beat_alert_ms: 5.0,
+            export_path: "performance".to_string(),
+            history: PerformanceHistory::default(),
+            view: PlotView::default(),
+            export_result: None,
+        }
     }
+}
 
-    graph(
-        app,
-        ui,
-        app.local_memory
-            .performance
-            .heartbeats
-            .iter()
-            .map(|&hb| {
-                (app.last_heartbeat
-                    .system_time
-                    .saturating_sub(hb.system_time)) as f32
-            })
-            .collect(),
-        app.local_memory
-            .performance
+/// Heartbeats as received, with the series plotted from them and from the network tallies.
+/// Bounded by the history length and point limit, as it is persisted.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct PerformanceHistory {
+    /// Heartbeats with the unix time in ms they were received at. At most the point limit of
+    /// the most recent are kept.
+    pub heartbeats: VecDeque<(i64, Heartbeat)>,
+    pub cpu_use_audio: TimeSeries,
+    pub process_freq_main: TimeSeries,
+    pub packet_rate: TimeSeries,
+    pub byte_rate: TimeSeries,
+    pub queue_depth: TimeSeries,
+    /// Time and rx tally totals at the last network sample.
+    #[serde(skip)]
+    last_sample: Option<(i64, usize, usize)>,
+    /// Deepest queue seen since the last network sample.
+    #[serde(skip)]
+    max_queue: usize,
+}
+
+impl PerformanceHistory {
+    fn trim(&mut self, since: i64, max_points: usize) {
+        while self
             .heartbeats
-            .iter()
-            .map(|&hb| hb.cpu_use_audio)
-            .collect(),
-        "Audio CPU load".to_string(),
-        None,
-        None,
-        Some(100.0),
-        Some(0.0),
+            .front()
+            .is_some_and(|(time, _)| *time < since)
+            || self.heartbeats.len() > max_points
+        {
+            self.heartbeats.pop_front();
+        }
+        for series in [
+            &mut self.cpu_use_audio,
+            &mut self.process_freq_main,
+            &mut self.packet_rate,
+            &mut self.byte_rate,
+            &mut self.queue_depth,
+        ] {
+            series.trim(since, max_points);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub fn record_heartbeat(app: &mut ClicksMonitorApp, heartbeat: &Heartbeat) {
+    let now = chrono::Utc::now().timestamp_millis();
+    let history = &mut app.local_memory.performance.history;
+    history.heartbeats.push_back((now, *heartbeat));
+    history.cpu_use_audio.push(now, heartbeat.cpu_use_audio);
+    history
+        .process_freq_main
+        .push(now, heartbeat.process_freq_main as f32 / 1000.0);
+}
+
+/// Sample the network tallies and queue depth once a second, and trim the history. Called
+/// every frame with the queue depth before it is drained.
+pub fn update(app: &mut ClicksMonitorApp, queue_depth: usize) {
+    let now = chrono::Utc::now().timestamp_millis();
+    let memory = &mut app.local_memory.performance;
+    let history = &mut memory.history;
+    history.max_queue = history.max_queue.max(queue_depth);
+    let (packets, bytes) = app
+        .udp_client
+        .rx_message_tally
+        .values()
+        .fold((0, 0), |(p, b), (count, size)| (p + count, b + size));
+    let Some((last_time, last_packets, last_bytes)) = history.last_sample else {
+        history.last_sample = Some((now, packets, bytes));
+        return;
+    };
+    if now - last_time < SAMPLE_INTERVAL_MS {
+        return;
+    }
+    let seconds = (now - last_time) as f32 / 1000.0;
+    history
+        .packet_rate
+        .push(now, packets.saturating_sub(last_packets) as f32 / seconds);
+    history.byte_rate.push(
+        now,
+        bytes.saturating_sub(last_bytes) as f32 / seconds / 1000.0,
     );
-    graph(
-        app,
-        ui,
-        app.local_memory
-            .performance
-            .heartbeats
-            .iter()
-            .map(|&hb| {
-                (app.last_heartbeat
-                    .system_time
-                    .saturating_sub(hb.system_time)) as f32
-            })
-            .collect(),
-        app.local_memory
-            .performance
-            .heartbeats
-            .iter()
-            .map(|&hb| (hb.process_freq_main as f32 / 1000.0).round())
-            .collect(),
-        "Processing frequency (kHz)".to_string(),
-        None,
-        None,
-        None,
-        None,
+    history.queue_depth.push(now, history.max_queue as f32);
+    history.max_queue = 0;
+    history.last_sample = Some((now, packets, bytes));
+    history.trim(
+        now - memory.history_minutes as i64 * 60_000,
+        memory.max_points,
     );
 }
 
-pub fn graph(
-    app: &mut ClicksMonitorApp,
-    ui: &mut egui::Ui,
-    x: Vec<f32>,
-    y: Vec<f32>,
-    label: String,
-    x_max_f: Option<f32>,
-    x_min_f: Option<f32>,
-    y_max_f: Option<f32>,
-    y_min_f: Option<f32>,
-) {
-    ui.vertical(|ui| {
-        ui.label(RichText::new(label).heading());
-
-        const SIZE: Vec2 = Vec2::new(1500.0, 640.0);
-        const EDGE_SIZE: Vec2 = Vec2::new(48.0, 32.0);
-        let graph_size = (SIZE - EDGE_SIZE) * Vec2::new(1.0, -1.0);
-        let (res, p) = ui.allocate_painter(SIZE, Sense::hover());
-        let origin = res.rect.left_bottom() + EDGE_SIZE * Vec2::new(1.0, -1.0);
-
-        p.rect_filled(res.rect, 0.0, Color32::BLACK);
-        //p.rect_filled(
-        //    Rect::from_points(&[origin, origin + graph_size]),
-        //    0.0,
-        //    Color32::BLACK.lerp_to_gamma(Color32::DARK_GRAY, 0.1),
-        //);
-
-        if x.len() != y.len() || x.len() == 0 || y.len() == 0 {
-            return;
-        }
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.horizontal(|ui| {
+            let memory = &mut app.local_memory.performance;
+            ui.add(
+                egui::Slider::new(&mut memory.history_minutes, 5..=1440)
+                    .logarithmic(true)
+                    .text("History (min)"),
+            );
+            memory
+                .view
+                .limit_span(memory.history_minutes as f64 * 60_000.0);
+            ui.add(
+                egui::Slider::new(&mut memory.max_points, 500..=20000)
+                    .logarithmic(true)
+                    .text("Points per series"),
+            )
+            .on_hover_text(
+                "Older samples are merged once a series has more points than this, and only \
+                 this many of the latest heartbeats are kept",
+            );
+            if ui
+                .add_enabled(!memory.view.following(), egui::Button::new("Follow live"))
+                .clicked()
+            {
+                memory.view.follow();
+            }
+            if ui.button("Clear history").clicked() {
+                memory.history.clear();
+            }
+        });
+        ui.label("Drag to pan, ctrl+scroll to zoom, double-click to follow live.");
+        export_controls(app, ui);
 
-        let mut x_max = *x
-            .iter()
-            .max_by(|x, y| x.abs().partial_cmp(&y.abs()).unwrap())
-            .unwrap_or(&0.0);
-
-        let mut x_min = *x
-            .iter()
-            .min_by(|x, y| x.abs().partial_cmp(&y.abs()).unwrap())
-            .unwrap_or(&0.0);
-
-        let mut y_max = *y
-            .iter()
-            .max_by(|x, y| x.abs().partial_cmp(&y.abs()).unwrap())
-            .unwrap_or(&0.0);
-
-        let mut y_min = *y
-            .iter()
-            .min_by(|x, y| x.abs().partial_cmp(&y.abs()).unwrap())
-            .unwrap_or(&0.0);
-
-        // let x_round_factor = 10_f32.powf(x_max.log10().ceil() + 1.0).ceil();
-        let y_round_factor = 10_f32.powf(y_max.log10().ceil() + 1.0).ceil();
-
-        x_min = x_min_f.unwrap_or(x_min);
-        x_max = x_max_f.unwrap_or(x_max);
-        y_min = y_min_f.unwrap_or(y_min);
-        y_max = y_max_f.unwrap_or(y_max);
-        //y_min = y_min_f.unwrap_or((y_min / y_round_factor).floor() * y_round_factor);
-        //y_max = y_max_f.unwrap_or((y_max / y_round_factor).ceil() * y_round_factor);
-
-        let x_range = 1.0_f32.max(x_max - x_min);
-        let y_range = 1.0_f32.max(y_max - y_min);
-
-        if !x_range.is_normal() || !y_range.is_normal() {
-            return;
-        }
+        egui::CollapsingHeader::new(RichText::new("Beat timing").heading())
+            .default_open(true)
+            .show(ui, |ui| beat_timing(app, ui));
+
+        // Alarm thresholds from the watchdog rules
+        let limits = |metric| watchdog::thresholds(app, metric);
+        let (cpu_limits, freq_limits, queue_limits) = (
+            limits(Metric::CpuUseAudio),
+            limits(Metric::ProcessFreqMain),
+            limits(Metric::QueueDepth),
+        );
+        let memory = &mut app.local_memory.performance;
+        let history = &memory.history;
+        let view = &mut memory.view;
+        let theme = &app.theme;
+        TimePlot::new("Audio CPU load")
+            .unit("%")
+            .series("CPU", &history.cpu_use_audio, Color32::WHITE)
+            .thresholds(cpu_limits)
+            .y_range(0.0, 100.0)
+            .height(PLOT_HEIGHT)
+            .show(ui, view, theme);
+        TimePlot::new("Processing frequency")
+            .unit(" kHz")
+            .series("Main loop", &history.process_freq_main, Color32::WHITE)
+            .thresholds(freq_limits)
+            .height(PLOT_HEIGHT)
+            .show(ui, view, theme);
+        TimePlot::new("Received packets")
+            .unit("/s")
+            .series("Packets", &history.packet_rate, Color32::WHITE)
+            .height(PLOT_HEIGHT)
+            .show(ui, view, theme);
+        TimePlot::new("Received data")
+            .unit(" kB/s")
+            .series("Data", &history.byte_rate, Color32::WHITE)
+            .height(PLOT_HEIGHT)
+            .show(ui, view, theme);
+        TimePlot::new("Message queue depth")
+            .series("Queued", &history.queue_depth, Color32::WHITE)
+            .thresholds(queue_limits)
+            .height(PLOT_HEIGHT)
+            .show(ui, view, theme);
+    });
+}
 
-        for i in 0..x.len() - 1 {
-            let x_i: f32 = x[i].clone().into();
-            let x_j: f32 = x[i + 1].clone().into();
-            let y_i: f32 = y[i].clone().into();
-            let y_j: f32 = y[i + 1].clone().into();
-            let p_a = origin
-                + (Vec2::new(x_i, y_i) - Vec2::new(x_min, y_min)) * graph_size
-                    / Vec2::new(x_range, y_range);
-            let p_b = origin
-                + (Vec2::new(x_j, y_j) - Vec2::new(x_min, y_min)) * graph_size
-                    / Vec2::new(x_range, y_range);
-            p.line_segment([p_a, p_b], Stroke::new(2.0, Color32::WHITE));
-            p.circle_filled(p_a, 2.0, Color32::WHITE);
+#[derive(serde::Serialize)]
+struct HeartbeatRow {
+    received: String,
+    received_ms: i64,
+    host_time: String,
+    cpu_use_audio: f32,
+    process_freq_main: u32,
+    uptime: u64,
+    common_version: String,
+    system_version: String,
+}
+
+#[derive(serde::Serialize)]
+struct TallyRow {
+    direction: &'static str,
+    kind: String,
+    count: usize,
+    bytes: usize,
+}
+
+#[derive(serde::Serialize)]
+struct PerformanceExport<'a> {
+    exported: String,
+    heartbeats: Vec<HeartbeatRow>,
+    /// Received packets per second.
+    packet_rate: &'a VecDeque<Sample>,
+    /// Received kB per second.
+    byte_rate: &'a VecDeque<Sample>,
+    queue_depth: &'a VecDeque<Sample>,
+    /// Totals since the monitor started.
+    tallies: Vec<TallyRow>,
+}
+
+fn timestamp(time_ms: i64) -> String {
+    DateTime::from_timestamp_millis(time_ms)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+fn heartbeat_rows(app: &ClicksMonitorApp) -> Vec<HeartbeatRow> {
+    app.local_memory
+        .performance
+        .history
+        .heartbeats
+        .iter()
+        .map(|(time, heartbeat)| HeartbeatRow {
+            received: timestamp(*time),
+            received_ms: *time,
+            host_time: timestamp(heartbeat.system_time as i64 * 1000),
+            cpu_use_audio: heartbeat.cpu_use_audio,
+            process_freq_main: heartbeat.process_freq_main,
+            uptime: heartbeat.uptime,
+            common_version: heartbeat.common_version.str().to_string(),
+            system_version: heartbeat.system_version.str().to_string(),
+        })
+        .collect()
+}
+
+fn tally_rows(app: &ClicksMonitorApp) -> Vec<TallyRow> {
+    let rx = MessageType::all().iter_names().map(|(name, kind)| {
+        let (count, bytes) = app
+            .udp_client
+            .rx_message_tally
+            .get(&kind)
+            .copied()
+            .unwrap_or_default();
+        TallyRow {
+            direction: "rx",
+            kind: name.to_string(),
+            count,
+            bytes,
         }
+    });
+    let tx = app
+        .udp_client
+        .tx_message_tally
+        .iter()
+        .map(|(kind, (count, bytes))| TallyRow {
+            direction: "tx",
+            kind: format!("{:?}", kind),
+            count: *count,
+            bytes: *bytes,
+        });
+    rx.chain(tx).collect()
+}
 
-        for (loc, anc, tex) in [
-            (origin, Align2::LEFT_TOP, x_min.to_string()),
-            (
-                origin + graph_size * Vec2::RIGHT,
-                Align2::RIGHT_TOP,
-                x_max.to_string(),
-            ),
-            (
-                origin + graph_size * Vec2::DOWN,
-                Align2::RIGHT_TOP,
-                y_max.to_string(),
-            ),
-            (origin, Align2::RIGHT_BOTTOM, y_min.to_string()),
-        ] {
-            p.text(
-                loc,
-                anc,
-                tex,
-                FontId::monospace(16.0),
-                app.theme.neutral_prim,
+fn export_json(app: &ClicksMonitorApp) -> String {
+    let history = &app.local_memory.performance.history;
+    serde_json::to_string_pretty(&PerformanceExport {
+        exported: timestamp(chrono::Utc::now().timestamp_millis()),
+        heartbeats: heartbeat_rows(app),
+        packet_rate: history.packet_rate.samples(),
+        byte_rate: history.byte_rate.samples(),
+        queue_depth: history.queue_depth.samples(),
+        tallies: tally_rows(app),
+    })
+    .unwrap_or_default()
+}
+
+/// One CSV file per table, as file name suffix and contents.
+fn export_csv(app: &ClicksMonitorApp) -> [(&'static str, String); 3] {
+    let mut heartbeats = String::new();
+    let _ = writeln!(
+        heartbeats,
+        "received,received_ms,host_time,cpu_use_audio,process_freq_main,uptime,common_version,\
+         system_version"
+    );
+    for row in heartbeat_rows(app) {
+        let _ = writeln!(
+            heartbeats,
+            "{},{},{},{:.1},{},{},\"{}\",\"{}\"",
+            row.received,
+            row.received_ms,
+            row.host_time,
+            row.cpu_use_audio,
+            row.process_freq_main,
+            row.uptime,
+            row.common_version.replace('"', "\"\""),
+            row.system_version.replace('"', "\"\"")
+        );
+    }
+
+    // The network series are sampled and downsampled together, so their samples line up
+    let history = &app.local_memory.performance.history;
+    let mut network = String::new();
+    let _ = writeln!(
+        network,
+        "time,time_ms,samples,packets_per_s,packets_per_s_max,kb_per_s,kb_per_s_max,queue_depth_max"
+    );
+    for ((packets, bytes), queue) in history
+        .packet_rate
+        .samples()
+        .iter()
+        .zip(history.byte_rate.samples())
+        .zip(history.queue_depth.samples())
+    {
+        let _ = writeln!(
+            network,
+            "{},{},{},{:.1},{:.1},{:.2},{:.2},{}",
+            timestamp(packets.time),
+            packets.time,
+            packets.count,
+            packets.mean,
+            packets.max,
+            bytes.mean,
+            bytes.max,
+            queue.max
+        );
+    }
+
+    let exported = timestamp(chrono::Utc::now().timestamp_millis());
+    let mut tallies = String::new();
+    let _ = writeln!(tallies, "exported,direction,type,count,bytes");
+    for row in tally_rows(app) {
+        let _ = writeln!(
+            tallies,
+            "{},{},{},{},{}",
+            exported, row.direction, row.kind, row.count, row.bytes
+        );
+    }
+
+    [
+        ("heartbeats", heartbeats),
+        ("network", network),
+        ("tallies", tallies),
+    ]
+}
+
+/// `base` with the current local time added, for export file names.
+pub fn timestamped(base: &str) -> String {
+    format!("{}-{}", base, chrono::Local::now().format("%Y%m%d-%H%M%S"))
+}
+
+/// Write a new file, refusing to replace an existing one. Returns the absolute path.
+pub fn save(path: &str, contents: &str) -> Result<String, String> {
+    let absolute = std::path::absolute(path).map_or(path.to_string(), |p| p.display().to_string());
+    std::fs::File::create_new(path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, contents.as_bytes()))
+        .map(|_| absolute.clone())
+        .map_err(|err| format!("Could not save {}: {}", absolute, err))
+}
+
+fn export_controls(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Export to");
+        egui::TextEdit::singleline(&mut app.local_memory.performance.export_path)
+            .desired_width(300.0)
+            .show(ui)
+            .response
+            .on_hover_text("The export time is added to the file names");
+        let base = timestamped(&app.local_memory.performance.export_path);
+        ui.menu_button("CSV", |ui| {
+            if ui.button("Save to files").clicked() {
+                let result = export_csv(app)
+                    .into_iter()
+                    .map(|(suffix, contents)| save(&format!("{}-{}.csv", base, suffix), &contents))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|paths| format!("Saved {}", paths.join(", ")));
+                app.local_memory.performance.export_result = Some(result);
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text("Heartbeats, network rates and message totals, one file each");
+        ui.menu_button("JSON", |ui| {
+            if ui.button("Save to file").clicked() {
+                app.local_memory.performance.export_result = Some(
+                    save(&format!("{}.json", base), &export_json(app))
+                        .map(|path| format!("Saved {}", path)),
+                );
+                ui.close_menu();
+            }
+            if ui.button("Copy to clipboard").clicked() {
+                ui.ctx().copy_text(export_json(app));
+                app.local_memory.performance.export_result =
+                    Some(Ok("Copied performance history".to_string()));
+                ui.close_menu();
+            }
+        });
+        match &app.local_memory.performance.export_result {
+            Some(Ok(text)) => ui.colored_label(app.theme.active_prim, text),
+            Some(Err(text)) => ui.colored_label(app.theme.err_prim, text),
+            None => ui.label(""),
+        };
+    });
+}
+
+/// Jitter statistics, histogram and drift plot of received beats against the ideal grid.
+fn beat_timing(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let stats = app
+        .beat_accuracy
+        .stats(app.last_heartbeat.process_freq_main);
+    ui.horizontal(|ui| {
+        egui::Grid::new("beat-timing-stats")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let stats = stats.unwrap_or_default();
+                for (label, value) in [
+                    ("Beats measured", app.beat_accuracy.errors.len().to_string()),
+                    ("Mean error", format!("{:+.2} ms", stats.mean)),
+                    ("Jitter (std. dev.)", format!("{:.2} ms", stats.std_dev)),
+                    ("Worst", format!("{:.2} ms", stats.max)),
+                    ("Core scheduling", format!("{:.3} ms", stats.core_std_dev)),
+                    (
+                        "Network and monitor",
+                        format!("{:.2} ms", stats.network_std_dev),
+                    ),
+                ] {
+                    ui.label(label);
+                    ui.label(RichText::new(value).monospace());
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+        ui.vertical(|ui| {
+            ui.add(
+                egui::Slider::new(&mut app.local_memory.performance.beat_alert_ms, 0.5..=50.0)
+                    .logarithmic(true)
+                    .text("Alert threshold (ms)"),
             );
-        }
+            match app.beat_accuracy.active_alert() {
+                Some(error) => ui.colored_label(
+                    app.theme.err_prim,
+                    format!("Beat {:+.1} ms off the grid", error),
+                ),
+                None => ui.colored_label(app.theme.active_prim, "Within threshold"),
+            };
+            ui.label(format!(
+                "{} alerts since cleared",
+                app.beat_accuracy.alert_count
+            ));
+            if ui.button("Clear").clicked() {
+                app.beat_accuracy.clear();
+            }
+        });
     });
+
+    ui.horizontal(|ui| {
+        jitter_histogram(app, ui);
+        let view = &mut app.local_memory.performance.view;
+        TimePlot::new("Drift from grid")
+            .unit(" ms")
+            .series("Drift", &app.beat_accuracy.drift, Color32::WHITE)
+            .height(TIMING_PLOT_SIZE.y)
+            .show(ui, view, &app.theme);
+    });
+}
+
+fn jitter_histogram(app: &ClicksMonitorApp, ui: &mut egui::Ui) {
+    let (resp, p) = ui.allocate_painter(TIMING_PLOT_SIZE, Sense::hover());
+    let rect = resp.rect.shrink2(Vec2::new(8.0, 16.0));
+    p.rect_filled(resp.rect, 0.0, Color32::BLACK);
+
+    let mut bins = [0usize; HISTOGRAM_BINS];
+    let bin_width = 2.0 * HISTOGRAM_RANGE_MS / HISTOGRAM_BINS as f32;
+    for error in &app.beat_accuracy.errors {
+        let bin =
+            ((error + HISTOGRAM_RANGE_MS) / bin_width).clamp(0.0, HISTOGRAM_BINS as f32 - 1.0);
+        bins[bin as usize] += 1;
+    }
+    let max = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let bar_width = rect.width() / HISTOGRAM_BINS as f32;
+    let threshold = app.local_memory.performance.beat_alert_ms;
+    for (i, count) in bins.iter().enumerate() {
+        let center = -HISTOGRAM_RANGE_MS + (i as f32 + 0.5) * bin_width;
+        let x = rect.left() + i as f32 * bar_width;
+        p.rect_filled(
+            Rect::from_min_max(
+                Pos2::new(x, rect.bottom() - rect.height() * *count as f32 / max),
+                Pos2::new(x + bar_width - 1.0, rect.bottom()),
+            ),
+            0.0,
+            if center.abs() > threshold {
+                app.theme.err_prim
+            } else {
+                app.theme.active_prim
+            },
+        );
+    }
+    for ms in [-threshold, threshold] {
+        if ms.abs() < HISTOGRAM_RANGE_MS {
+            let x = rect.center().x + ms / HISTOGRAM_RANGE_MS * rect.width() / 2.0;
+            p.vline(x, rect.y_range(), Stroke::new(1.0, app.theme.warn_prim));
+        }
+    }
+    p.vline(
+        rect.center().x,
+        rect.y_range(),
+        Stroke::new(1.0, app.theme.neutral_prim),
+    );
+    for (pos, align, text) in [
+        (
+            rect.left_bottom(),
+            Align2::LEFT_TOP,
+            format!("-{} ms", HISTOGRAM_RANGE_MS),
+        ),
+        (rect.center_bottom(), Align2::CENTER_TOP, "0".to_string()),
+        (
+            rect.right_bottom(),
+            Align2::RIGHT_TOP,
+            format!("+{} ms", HISTOGRAM_RANGE_MS),
+        ),
+        (
+            rect.left_top(),
+            Align2::LEFT_BOTTOM,
+            "Beat interval error".to_string(),
+        ),
+    ] {
+        p.text(
+            pos,
+            align,
+            text,
+            FontId::monospace(12.0),
+            app.theme.neutral_prim,
+        );
+    }
 }
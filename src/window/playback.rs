@@ -1,41 +1,132 @@
 use crate::app::ClicksMonitorApp;
+use crate::widget::waveform::waveform_thumbnail;
 use common::event::EventDescription;
-use common::local::status::{AudioSourceState, PlaybackState};
+use common::local::status::{AudioSourceState, ClipMetadata, NO_CLIP, PlaybackState};
 use common::protocol::request::{ControlAction, Request};
-use egui::{Align, Button, Color32, Label, ProgressBar, RichText, Sense};
+use egui::{Align, Align2, Button, Color32, Label, ProgressBar, RichText, Sense, Vec2};
 use egui::{Grid, Widget};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const MIN_GAIN: f32 = -48.0;
+const DEFAULT_FADE_OUT: f32 = 3.0;
+const FADE_STEP: Duration = Duration::from_millis(50);
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 pub struct PlaybackWindowMemory {
-    pub clip_cue_list: HashMap<u8, u16>,
+    pub cue_stack: Vec<ClipCue>,
+    pub selected_cue: usize,
+    pub alerts: CountdownAlerts,
+    #[serde(skip)]
+    pub clip_search: String,
+    /// Offset for clips started from the clip buttons, in mm:ss.ms.
+    #[serde(skip)]
+    pub start_offset: String,
+    /// Stack index of each cue in its pre-wait, and when it fires.
+    #[serde(skip)]
+    waiting: Vec<(Instant, usize)>,
+    #[serde(skip)]
+    fades: Vec<ChannelFade>,
+    /// Channel and clip whose full-screen countdown was dismissed.
+    #[serde(skip)]
+    countdown_dismissed: Option<(u8, u16)>,
+}
+
+/// Remaining-time thresholds for playing clips, in seconds.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy)]
+#[serde(default)]
+pub struct CountdownAlerts {
+    pub warn_seconds: f32,
+    pub alert_seconds: f32,
+    /// Channel to show a full-screen countdown for once it passes the warning threshold.
+    pub fullscreen_channel: Option<u8>,
+}
+
+impl Default for CountdownAlerts {
+    fn default() -> Self {
+        Self {
+            warn_seconds: 10.0,
+            alert_seconds: 3.0,
+            fullscreen_channel: None,
+        }
+    }
+}
+
+/// One entry in the playback cue stack.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq)]
+pub struct ClipCue {
+    pub channel: u8,
+    pub clip: u16,
+    /// Start position in the clip, in seconds.
+    pub start: f32,
+    /// Fade length in seconds, used both when starting and when stopping with fade.
+    pub fade: f32,
+    /// Delay between GO and the clip starting, in seconds.
+    pub pre_wait: f32,
+}
+
+impl ClipCue {
+    pub fn new(channel: u8, clip: u16) -> Self {
+        Self {
+            channel,
+            clip,
+            start: 0.0,
+            fade: 0.0,
+            pre_wait: 0.0,
+        }
+    }
+}
+
+/// A gain ramp on a playback channel, done from the monitor since playback events have no
+/// fade parameter. Stopping fades restore the channel gain once the clip has stopped.
+struct ChannelFade {
+    channel: u8,
+    from: f32,
+    to: f32,
+    start: Instant,
+    length: f32,
+    stop_at_end: Option<f32>,
+    last_sent: Instant,
 }
 
 pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.label(RichText::new("Playback").heading());
         ui.horizontal(|ui| {
-            ui.set_width(500.0);
-            if app.local_memory.playback.clip_cue_list.is_empty() {
+            if !app.local_memory.security.allow_interaction {
                 ui.disable()
             }
-            ui.label(format!(
-                "{} clip(s) cued",
-                app.local_memory.playback.clip_cue_list.len(),
-            ));
-            if ui.button("Play").clicked() && app.local_memory.security.allow_interaction {
-                play_clip_cue(app, ui);
+            let stack_len = app.local_memory.playback.cue_stack.len();
+            let selected = app.local_memory.playback.selected_cue;
+            ui.label(format!("{} cue(s) in stack", stack_len));
+            if ui
+                .add_enabled(
+                    selected < stack_len,
+                    Button::new(RichText::new("GO").monospace()).fill(app.theme.cued_prim),
+                )
+                .clicked()
+            {
+                go(app);
             };
-            if ui.button("Once").clicked() && app.local_memory.security.allow_interaction {
-                play_clip_cue(app, ui);
-                app.local_memory.playback.clip_cue_list.clear();
+            if ui
+                .add_enabled(selected < stack_len, Button::new("Stop selected"))
+                .clicked()
+            {
+                stop_cue(app, selected, false);
             };
-            if ui.button("Stop").clicked() && app.local_memory.security.allow_interaction {
-                play_clip_cue(app, ui);
+            if ui
+                .add_enabled(selected < stack_len, Button::new("Fade out selected"))
+                .clicked()
+            {
+                stop_cue(app, selected, true);
             };
-            if ui.button("Clear").clicked() && app.local_memory.security.allow_interaction {
-                app.local_memory.playback.clip_cue_list.clear();
+            if ui
+                .add_enabled(stack_len > 0, Button::new("Clear stack"))
+                .clicked()
+            {
+                app.local_memory.playback.cue_stack.clear();
+                app.local_memory.playback.waiting.clear();
+                app.local_memory.playback.selected_cue = 0;
             };
         });
         if Button::new(RichText::new("PANIC (Stop all)").monospace())
@@ -47,6 +138,36 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             stop_all(app, ui);
         };
     });
+    egui::CollapsingHeader::new("Countdown alerts").show(ui, |ui| {
+        alert_settings(app, ui);
+    });
+    egui::CollapsingHeader::new("Cue stack")
+        .default_open(true)
+        .show(ui, |ui| {
+            cue_stack(app, ui);
+        });
+    ui.horizontal(|ui| {
+        ui.label("Start at");
+        let valid = parse_offset(&app.local_memory.playback.start_offset).is_some();
+        egui::TextEdit::singleline(&mut app.local_memory.playback.start_offset)
+            .hint_text("mm:ss.ms")
+            .desired_width(80.0)
+            .text_color_opt((!valid).then_some(app.theme.err_prim))
+            .ui(ui)
+            .on_hover_text("Offset used when starting clips from the clip buttons and search");
+        ui.label("Find clip");
+        egui::TextEdit::singleline(&mut app.local_memory.playback.clip_search)
+            .hint_text("Clip name or number")
+            .desired_width(300.0)
+            .ui(ui);
+        if ui.button("Clear").clicked() {
+            app.local_memory.playback.clip_search.clear();
+        }
+    });
+    if !app.local_memory.playback.clip_search.trim().is_empty() {
+        clip_search_results(app, ui);
+        ui.separator();
+    }
     Grid::new("playback-channel-grid")
         .num_columns(5)
         .striped(true)
@@ -92,22 +213,23 @@ pub fn render_channel_slice(app: &mut ClicksMonitorApp, ui: &mut egui::Ui, index
     ui.label(channel_name);
 
     // Time bar
-    ui.label(format!(
-        "{:02.0}:{:02.0}.{:03.0}",
-        (seconds_left / 60.0).floor(),
-        (seconds_left % 60.0).floor(),
-        (seconds_left * 1000.0 % 1000.0).floor()
-    ));
-    ProgressBar::new(
+    let countdown_color = countdown_color(app, seconds_left);
+    if source.playing {
+        ui.colored_label(countdown_color, format_duration(seconds_left));
+    } else {
+        ui.label(format_duration(seconds_left));
+    }
+    let bar = ProgressBar::new(
         source.current_sample as f32 / source.clip_length as f32 * source.playing as usize as f32,
     )
     .fill(if source.playing {
-        app.theme.active_prim
+        countdown_color
     } else if app
         .local_memory
         .playback
-        .clip_cue_list
-        .contains_key(&source.channel)
+        .cue_stack
+        .iter()
+        .any(|c| c.channel == source.channel)
     {
         app.theme.cued_prim
     } else {
@@ -115,12 +237,8 @@ pub fn render_channel_slice(app: &mut ClicksMonitorApp, ui: &mut egui::Ui, index
     })
     .desired_width(500.0)
     .ui(ui);
-    ui.label(format!(
-        "{:02.0}:{:02.0}.{:03.0}",
-        (seconds_total / 60.0).floor(),
-        (seconds_total % 60.0).floor(),
-        (seconds_total * 1000.0 % 1000.0).floor()
-    ));
+    scrub_bar(app, ui, &bar, source);
+    ui.label(format_duration(seconds_total));
 
     // Clips
     ui.horizontal(|ui| {
@@ -147,20 +265,17 @@ pub fn render_clip(
     selected: bool,
     status: PlaybackState,
 ) {
-    let real_clip = clip < 2048;
-    // FIXME: magic number for "usize::MAX
-    // of the core machine, which we can't
-    // guarantee is the same as on this
-    // machine
-    egui::Frame::new()
+    let real_clip = clip != NO_CLIP;
+    let info = app.clip_info.get(&(status.channel, clip)).copied();
+    let frame = egui::Frame::new()
         .fill(if selected {
             app.theme.active_prim
         } else if app
             .local_memory
             .playback
-            .clip_cue_list
-            .get(&status.channel)
-            .is_some_and(|c| *c as u16 == clip)
+            .cue_stack
+            .iter()
+            .any(|c| c.channel == status.channel && c.clip == clip)
         {
             app.theme.cued_prim
         } else if real_clip {
@@ -170,7 +285,7 @@ pub fn render_clip(
         })
         .show(ui, |ui| {
             ui.horizontal_centered(|ui| {
-                ui.set_width(64.0);
+                ui.set_width(128.0);
                 ui.set_height(16.0);
 
                 if !real_clip {
@@ -186,8 +301,8 @@ pub fn render_clip(
                 if cue_button.clicked() && app.local_memory.security.allow_interaction {
                     app.local_memory
                         .playback
-                        .clip_cue_list
-                        .insert(status.channel, clip);
+                        .cue_stack
+                        .push(ClipCue::new(status.channel, clip));
                 }
 
                 let play_button = Label::new(if status.playing && selected {
@@ -208,7 +323,7 @@ pub fn render_clip(
                                 }
                             } else {
                                 EventDescription::PlaybackEvent {
-                                    sample: 0,
+                                    sample: start_offset_sample(app, status.channel, clip),
                                     channel_idx: status.channel as u16,
                                     clip_idx: clip,
                                 }
@@ -216,25 +331,699 @@ pub fn render_clip(
                         )));
                 }
 
-                ui.label(clip.to_string());
+                Label::new(clip_name(app, status.channel, clip))
+                    .truncate()
+                    .ui(ui);
+                if let Some(info) = info {
+                    waveform_thumbnail(
+                        ui,
+                        &info.waveform,
+                        Vec2::new(ui.available_width(), 14.0),
+                        app.theme.neutral_prim,
+                    );
+                }
             });
         });
+    if real_clip {
+        frame
+            .response
+            .on_hover_text(clip_description(app, status.channel, clip));
+    }
+}
+
+pub fn format_duration(seconds: f32) -> String {
+    format!(
+        "{:02.0}:{:02.0}.{:03.0}",
+        (seconds / 60.0).floor(),
+        (seconds % 60.0).floor(),
+        (seconds * 1000.0 % 1000.0).floor()
+    )
+}
+
+/// Parse an offset written as mm:ss.ms, or plain seconds. Empty text means the clip start.
+pub fn parse_offset(text: &str) -> Option<f32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(0.0);
+    }
+    let seconds = match text.split_once(':') {
+        Some((minutes, seconds)) => {
+            let seconds: f32 = seconds.parse().ok()?;
+            if !(0.0..60.0).contains(&seconds) {
+                return None;
+            }
+            minutes.parse::<u32>().ok()? as f32 * 60.0 + seconds
+        }
+        None => text.parse().ok()?,
+    };
+    (seconds.is_finite() && seconds >= 0.0).then_some(seconds)
+}
+
+/// Sample position of an offset into a clip, kept within the clip when its length is known.
+fn clip_sample(app: &ClicksMonitorApp, channel: u8, clip: u16, seconds: f32) -> i32 {
+    let sample = (seconds * app.status.jack_status.sample_rate as f32) as i64;
+    let length = app
+        .clip_info
+        .get(&(channel, clip))
+        .map_or(i32::MAX as i64, |info| info.length as i64);
+    sample.clamp(0, length.min(i32::MAX as i64)) as i32
+}
+
+fn start_offset_sample(app: &ClicksMonitorApp, channel: u8, clip: u16) -> i32 {
+    clip_sample(
+        app,
+        channel,
+        clip,
+        parse_offset(&app.local_memory.playback.start_offset).unwrap_or(0.0),
+    )
 }
 
-pub fn play_clip_cue(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
-    for (channel, clip) in app.local_memory.playback.clip_cue_list.clone() {
+/// Make a channel's progress bar clickable. Clicking or dragging seeks the playing clip, or
+/// starts the channel's first stacked cue at that position if nothing is playing.
+fn scrub_bar(
+    app: &mut ClicksMonitorApp,
+    ui: &mut egui::Ui,
+    bar: &egui::Response,
+    source: PlaybackState,
+) {
+    let target = if source.playing {
+        Some((source.clip_idx, source.clip_length))
+    } else {
+        app.local_memory
+            .playback
+            .cue_stack
+            .iter()
+            .find(|c| c.channel == source.channel)
+            .and_then(|c| {
+                app.clip_info
+                    .get(&(c.channel, c.clip))
+                    .map(|info| (c.clip, info.length))
+            })
+    };
+    let sense = if target.is_some() && app.local_memory.security.allow_interaction {
+        Sense::click_and_drag()
+    } else {
+        Sense::hover()
+    };
+    let resp = ui.interact(bar.rect, bar.id.with("scrub"), sense);
+    let Some((clip, length)) = target else {
+        resp.on_hover_text("Play or cue a clip on this channel to seek in it");
+        return;
+    };
+    let Some(pos) = resp.hover_pos().or(resp.interact_pointer_pos()) else {
+        return;
+    };
+    let fraction = ((pos.x - bar.rect.left()) / bar.rect.width()).clamp(0.0, 1.0);
+    let sample = (fraction * length as f32) as i32;
+    let seconds = if app.status.jack_status.sample_rate != 0 {
+        sample as f32 / app.status.jack_status.sample_rate as f32
+    } else {
+        0.0
+    };
+    ui.painter().vline(
+        bar.rect.left() + fraction * bar.rect.width(),
+        bar.rect.y_range(),
+        egui::Stroke::new(1.0, app.theme.neutral_prim),
+    );
+
+    if resp.clicked() || resp.drag_stopped() {
         app.udp_client
             .send_msg(Request::ControlAction(ControlAction::RunEvent(
                 EventDescription::PlaybackEvent {
-                    sample: 0,
-                    channel_idx: channel as u16,
+                    sample,
+                    channel_idx: source.channel as u16,
                     clip_idx: clip,
                 },
             )));
     }
+    resp.on_hover_text(format!(
+        "{} {}",
+        if source.playing {
+            "Seek to"
+        } else {
+            "Start at"
+        },
+        format_duration(seconds)
+    ));
+}
+
+fn clip_seconds(app: &ClicksMonitorApp, info: &ClipMetadata) -> f32 {
+    if app.status.jack_status.sample_rate != 0 {
+        info.length as f32 / app.status.jack_status.sample_rate as f32
+    } else {
+        0.0
+    }
+}
+
+fn channel_layout(num_channels: u8) -> String {
+    match num_channels {
+        1 => "Mono".to_string(),
+        2 => "Stereo".to_string(),
+        n => format!("{} ch", n),
+    }
+}
+
+pub fn clip_name(app: &ClicksMonitorApp, channel: u8, clip: u16) -> String {
+    match app.clip_info.get(&(channel, clip)) {
+        Some(info) if !info.name.is_empty() => info.name.str().to_string(),
+        _ => format!("Clip {}", clip),
+    }
+}
+
+fn clip_description(app: &ClicksMonitorApp, channel: u8, clip: u16) -> String {
+    match app.clip_info.get(&(channel, clip)) {
+        Some(info) => format!(
+            "Clip {}: {}\n{}, {}",
+            clip,
+            info.name.str(),
+            format_duration(clip_seconds(app, info)),
+            channel_layout(info.num_channels)
+        ),
+        None => format!("Clip {} (no metadata from core)", clip),
+    }
+}
+
+/// List every clip on every channel whose name or number matches the search text.
+fn clip_search_results(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let query = app.local_memory.playback.clip_search.trim().to_lowercase();
+    let mut matches = vec![];
+    for (channel, clips) in app.status.playback_status.clips.iter().enumerate() {
+        for clip in clips.iter().copied().filter(|c| *c != NO_CLIP) {
+            let name = clip_name(app, channel as u8, clip);
+            if name.to_lowercase().contains(&query) || clip.to_string() == query {
+                matches.push((channel as u8, clip, name));
+            }
+        }
+    }
+
+    if matches.is_empty() {
+        ui.label("No matching clips");
+        return;
+    }
+
+    egui::ScrollArea::vertical()
+        .id_salt("clip-search-scroll")
+        .max_height(240.0)
+        .show(ui, |ui| {
+            Grid::new("clip-search-grid")
+                .num_columns(7)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Ch. #");
+                    ui.label("Channel");
+                    ui.label("Clip");
+                    ui.label("Length");
+                    ui.label("Layout");
+                    ui.label("Waveform");
+                    ui.label("");
+                    ui.end_row();
+                    for (channel, clip, name) in matches {
+                        let info = app.clip_info.get(&(channel, clip)).copied();
+                        ui.label(format!("{:02}", channel + 1));
+                        ui.label(
+                            app.system_config.channels[channel as usize + 2]
+                                .name
+                                .str()
+                                .to_string(),
+                        );
+                        ui.label(format!("{} ({})", name, clip));
+                        match info {
+                            Some(info) => {
+                                ui.label(format_duration(clip_seconds(app, &info)));
+                                ui.label(channel_layout(info.num_channels));
+                                waveform_thumbnail(
+                                    ui,
+                                    &info.waveform,
+                                    Vec2::new(128.0, 16.0),
+                                    app.theme.neutral_prim,
+                                );
+                            }
+                            None => {
+                                ui.label("-");
+                                ui.label("-");
+                                ui.label("");
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            if !app.local_memory.security.allow_interaction {
+                                ui.disable();
+                            }
+                            if ui.button("Cue").clicked() {
+                                app.local_memory
+                                    .playback
+                                    .cue_stack
+                                    .push(ClipCue::new(channel, clip));
+                            }
+                            if ui.button("Play").clicked() {
+                                app.udp_client.send_msg(Request::ControlAction(
+                                    ControlAction::RunEvent(EventDescription::PlaybackEvent {
+                                        sample: start_offset_sample(app, channel, clip),
+                                        channel_idx: channel as u16,
+                                        clip_idx: clip,
+                                    }),
+                                ));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn alert_settings(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let alerts = &mut app.local_memory.playback.alerts;
+    ui.horizontal(|ui| {
+        ui.label("Warn at");
+        egui::DragValue::new(&mut alerts.warn_seconds)
+            .range(0.0..=120.0)
+            .suffix(" s")
+            .ui(ui);
+        ui.label("Alert at");
+        egui::DragValue::new(&mut alerts.alert_seconds)
+            .range(0.0..=alerts.warn_seconds)
+            .suffix(" s")
+            .ui(ui);
+        ui.label("Full-screen countdown");
+        egui::ComboBox::new("countdown-channel", "")
+            .selected_text(match alerts.fullscreen_channel {
+                Some(channel) => format!(
+                    "{:02} {}",
+                    channel + 1,
+                    app.system_config.channels[channel as usize + 2].name.str()
+                ),
+                None => "Off".to_string(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut alerts.fullscreen_channel, None, "Off");
+                for channel in 0..30 {
+                    ui.selectable_value(
+                        &mut alerts.fullscreen_channel,
+                        Some(channel),
+                        format!(
+                            "{:02} {}",
+                            channel + 1,
+                            app.system_config.channels[channel as usize + 2].name.str()
+                        ),
+                    );
+                }
+            });
+    });
+}
+
+/// Seconds left of the clip playing on a playback channel, if any.
+pub fn seconds_left(app: &ClicksMonitorApp, channel: u8) -> Option<f32> {
+    match app.status.sources.get(channel as usize + 2) {
+        Some(AudioSourceState::PlaybackStatus(status))
+            if status.playing && app.status.jack_status.sample_rate != 0 =>
+        {
+            Some(
+                (status.clip_length as f32 - status.current_sample as f32).max(0.0)
+                    / app.status.jack_status.sample_rate as f32,
+            )
+        }
+        _ => None,
+    }
+}
+
+/// All playing channels with their remaining time, soonest to end first.
+pub fn remaining_times(app: &ClicksMonitorApp) -> Vec<(u8, f32)> {
+    (0..30)
+        .filter_map(|channel| seconds_left(app, channel).map(|seconds| (channel, seconds)))
+        .sorted_by(|a, b| a.1.total_cmp(&b.1))
+        .collect()
+}
+
+pub fn countdown_color(app: &ClicksMonitorApp, seconds: f32) -> Color32 {
+    let alerts = app.local_memory.playback.alerts;
+    if seconds <= alerts.alert_seconds {
+        app.theme.err_prim
+    } else if seconds <= alerts.warn_seconds {
+        app.theme.warn_prim
+    } else {
+        app.theme.active_prim
+    }
+}
+
+/// Full-screen countdown for the chosen channel once its clip passes the warning threshold.
+/// It flashes below the alert threshold, and clicking dismisses it until the next clip.
+pub fn countdown_overlay(app: &mut ClicksMonitorApp, ctx: &egui::Context) {
+    let Some(channel) = app.local_memory.playback.alerts.fullscreen_channel else {
+        return;
+    };
+    let (Some(seconds), AudioSourceState::PlaybackStatus(status)) = (
+        seconds_left(app, channel),
+        app.status.sources[channel as usize + 2],
+    ) else {
+        return;
+    };
+    let alerts = app.local_memory.playback.alerts;
+    if seconds > alerts.warn_seconds
+        || app.local_memory.playback.countdown_dismissed == Some((channel, status.clip_idx))
+    {
+        return;
+    }
+
+    let color = countdown_color(app, seconds);
+    let flash = seconds <= alerts.alert_seconds && ctx.input(|i| i.time * 2.0) as i64 % 2 == 0;
+    let screen = ctx.screen_rect();
+    egui::Area::new(egui::Id::new("playback-countdown"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(screen.min)
+        .show(ctx, |ui| {
+            let (resp, p) = ui.allocate_painter(screen.size(), Sense::click());
+            p.rect_filled(
+                screen,
+                0.0,
+                if flash {
+                    color
+                } else {
+                    app.theme.base.gamma_multiply(0.95)
+                },
+            );
+            p.text(
+                screen.center() - Vec2::new(0.0, screen.height() * 0.25),
+                Align2::CENTER_CENTER,
+                format!(
+                    "{:02} {}  {}",
+                    channel + 1,
+                    app.system_config.channels[channel as usize + 2].name.str(),
+                    clip_name(app, channel, status.clip_idx)
+                ),
+                egui::FontId::proportional(screen.height() * 0.06),
+                app.theme.neutral_prim,
+            );
+            p.text(
+                screen.center(),
+                Align2::CENTER_CENTER,
+                format!("{:.1}", seconds),
+                egui::FontId::monospace(screen.height() * 0.4),
+                if flash { app.theme.base } else { color },
+            );
+            p.text(
+                screen.center_bottom() - Vec2::new(0.0, 24.0),
+                Align2::CENTER_BOTTOM,
+                "Click to dismiss",
+                egui::FontId::proportional(16.0),
+                app.theme.neutral_prim,
+            );
+            if resp.clicked() {
+                app.local_memory.playback.countdown_dismissed = Some((channel, status.clip_idx));
+            }
+        });
+}
+
+fn cue_stack(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    if app.local_memory.playback.cue_stack.is_empty() {
+        ui.label("No cues. Use ☉ on a clip to add it to the stack.");
+        return;
+    }
+    if !app.local_memory.security.allow_interaction {
+        ui.disable();
+    }
+    let mut move_up = None;
+    let mut remove = None;
+    let mut stop = None;
+    Grid::new("playback-cue-stack")
+        .num_columns(9)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("#");
+            ui.label("Ch. #");
+            ui.label("Clip");
+            ui.label("Start");
+            ui.label("Fade (s)");
+            ui.label("Pre-wait (s)");
+            ui.label("State");
+            ui.label("");
+            ui.end_row();
+            for i in 0..app.local_memory.playback.cue_stack.len() {
+                let cue = app.local_memory.playback.cue_stack[i];
+                let selected = app.local_memory.playback.selected_cue == i;
+                if ui
+                    .selectable_label(
+                        selected,
+                        format!("{}{:>3}", if selected { "▶" } else { " " }, i + 1),
+                    )
+                    .clicked()
+                {
+                    app.local_memory.playback.selected_cue = i;
+                }
+                ui.label(format!("{:02}", cue.channel + 1));
+                ui.label(clip_name(app, cue.channel, cue.clip));
+
+                let max_start = app
+                    .clip_info
+                    .get(&(cue.channel, cue.clip))
+                    .map(|info| clip_seconds(app, info))
+                    .filter(|seconds| *seconds > 0.0)
+                    .unwrap_or(3600.0);
+                let entry = &mut app.local_memory.playback.cue_stack[i];
+                egui::DragValue::new(&mut entry.start)
+                    .range(0.0..=max_start)
+                    .speed(0.1)
+                    .custom_formatter(|v, _| format_duration(v as f32))
+                    .custom_parser(|text| parse_offset(text).map(f64::from))
+                    .ui(ui);
+                egui::DragValue::new(&mut entry.fade)
+                    .range(0.0..=60.0)
+                    .speed(0.1)
+                    .ui(ui);
+                egui::DragValue::new(&mut entry.pre_wait)
+                    .range(0.0..=600.0)
+                    .speed(0.1)
+                    .ui(ui);
+
+                ui.label(cue_state(app, i, &cue));
+
+                ui.horizontal(|ui| {
+                    if ui.small_button("⏹").on_hover_text("Stop").clicked() {
+                        stop = Some((i, false));
+                    }
+                    if ui.small_button("↘").on_hover_text("Fade out").clicked() {
+                        stop = Some((i, true));
+                    }
+                    if ui
+                        .add_enabled(i > 0, Button::new("⏶").small())
+                        .on_hover_text("Move up")
+                        .clicked()
+                    {
+                        move_up = Some(i);
+                    }
+                    if ui.small_button("✕").on_hover_text("Remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+    // Stop before the stack is rearranged, while the index still points at the same cue
+    if let Some((i, fade)) = stop {
+        stop_cue(app, i, fade);
+    }
+    let memory = &mut app.local_memory.playback;
+    if let Some(i) = move_up {
+        memory.cue_stack.swap(i, i - 1);
+        // The selection and pre-waits follow the cues they belong to
+        let swapped = |idx: usize| match idx {
+            idx if idx == i => i - 1,
+            idx if idx == i - 1 => i,
+            idx => idx,
+        };
+        memory.selected_cue = swapped(memory.selected_cue);
+        for (_, idx) in memory.waiting.iter_mut() {
+            *idx = swapped(*idx);
+        }
+    }
+    if let Some(i) = remove {
+        memory.cue_stack.remove(i);
+        if memory.selected_cue > i {
+            memory.selected_cue -= 1;
+        }
+        memory.waiting.retain(|(_, idx)| *idx != i);
+        for (_, idx) in memory.waiting.iter_mut() {
+            if *idx > i {
+                *idx -= 1;
+            }
+        }
+    }
+    memory.selected_cue = memory.selected_cue.min(memory.cue_stack.len());
+}
+
+fn cue_state(app: &ClicksMonitorApp, idx: usize, cue: &ClipCue) -> String {
+    let memory = &app.local_memory.playback;
+    if let Some((time, _)) = memory.waiting.iter().find(|(_, i)| *i == idx) {
+        return format!(
+            "Pre-wait {:.1}s",
+            time.saturating_duration_since(Instant::now()).as_secs_f32()
+        );
+    }
+    let playing = match app.status.sources.get(cue.channel as usize + 2) {
+        Some(AudioSourceState::PlaybackStatus(status)) => {
+            status.playing && status.clip_idx == cue.clip
+        }
+        _ => false,
+    };
+    if !playing {
+        return "".to_string();
+    }
+    if memory.fades.iter().any(|f| f.channel == cue.channel) {
+        "Fading".to_string()
+    } else {
+        "Playing".to_string()
+    }
+}
+
+/// Start the selected cue (after its pre-wait) and move the selection to the next one.
+pub fn go(app: &mut ClicksMonitorApp) {
+    let memory = &mut app.local_memory.playback;
+    let Some(cue) = memory.cue_stack.get(memory.selected_cue).copied() else {
+        return;
+    };
+    memory.waiting.push((
+        Instant::now() + Duration::from_secs_f32(cue.pre_wait),
+        memory.selected_cue,
+    ));
+    memory.selected_cue += 1;
+}
+
+fn fire(app: &mut ClicksMonitorApp, cue: ClipCue) {
+    let sample = clip_sample(app, cue.channel, cue.clip, cue.start);
+    // A fade still running on the channel would fight the new cue, and the current gain is
+    // somewhere in the middle of it, so take over the gain it was heading for
+    let fades = &mut app.local_memory.playback.fades;
+    let running = fades
+        .iter()
+        .position(|f| f.channel == cue.channel)
+        .map(|idx| fades.remove(idx));
+    let target = running
+        .as_ref()
+        .map_or(app.sources_gains[cue.channel as usize + 2], |f| {
+            f.stop_at_end.unwrap_or(f.to)
+        });
+    if cue.fade > 0.0 {
+        set_playback_gain(app, cue.channel, MIN_GAIN);
+        app.local_memory.playback.fades.push(ChannelFade {
+            channel: cue.channel,
+            from: MIN_GAIN,
+            to: target,
+            start: Instant::now(),
+            length: cue.fade,
+            stop_at_end: None,
+            last_sent: Instant::now(),
+        });
+    } else if running.is_some() {
+        set_playback_gain(app, cue.channel, target);
+    }
+    app.udp_client
+        .send_msg(Request::ControlAction(ControlAction::RunEvent(
+            EventDescription::PlaybackEvent {
+                sample,
+                channel_idx: cue.channel as u16,
+                clip_idx: cue.clip,
+            },
+        )));
+}
+
+/// Stop the channel of the cue at a stack index, either at once or by fading it out first. Also
+/// cancels that stack entry if it is still in its pre-wait.
+pub fn stop_cue(app: &mut ClicksMonitorApp, idx: usize, fade: bool) {
+    let memory = &mut app.local_memory.playback;
+    let Some(cue) = memory.cue_stack.get(idx).copied() else {
+        return;
+    };
+    memory.waiting.retain(|(_, i)| *i != idx);
+    if !fade {
+        memory.fades.retain(|f| f.channel != cue.channel);
+        app.udp_client
+            .send_msg(Request::ControlAction(ControlAction::RunEvent(
+                EventDescription::PlaybackStopEvent {
+                    channel_idx: cue.channel as u16,
+                },
+            )));
+        return;
+    }
+    let current = app.sources_gains[cue.channel as usize + 2];
+    let restore = memory
+        .fades
+        .iter()
+        .find(|f| f.channel == cue.channel)
+        .map_or(current, |f| f.stop_at_end.unwrap_or(f.to));
+    memory.fades.retain(|f| f.channel != cue.channel);
+    memory.fades.push(ChannelFade {
+        channel: cue.channel,
+        from: current,
+        to: MIN_GAIN,
+        start: Instant::now(),
+        length: if cue.fade > 0.0 {
+            cue.fade
+        } else {
+            DEFAULT_FADE_OUT
+        },
+        stop_at_end: Some(restore),
+        last_sent: Instant::now(),
+    });
+}
+
+fn set_playback_gain(app: &mut ClicksMonitorApp, channel: u8, gain: f32) {
+    app.sources_gains[channel as usize + 2] = gain;
+    app.udp_client
+        .send_msg(Request::ControlAction(ControlAction::SetChannelGain(
+            channel + 2,
+            gain,
+        )));
+}
+
+/// Run pre-waits and fades. Called every frame regardless of which tab is shown.
+pub fn update(app: &mut ClicksMonitorApp) {
+    let now = Instant::now();
+    let (due, waiting): (Vec<_>, Vec<_>) = app
+        .local_memory
+        .playback
+        .waiting
+        .drain(..)
+        .partition(|(time, _)| *time <= now);
+    app.local_memory.playback.waiting = waiting;
+    for (_, idx) in due {
+        if let Some(cue) = app.local_memory.playback.cue_stack.get(idx).copied() {
+            fire(app, cue);
+        }
+    }
+
+    let mut fades = std::mem::take(&mut app.local_memory.playback.fades);
+    fades.retain_mut(|fade| {
+        let progress = (fade.start.elapsed().as_secs_f32() / fade.length).min(1.0);
+        if progress < 1.0 && fade.last_sent.elapsed() < FADE_STEP {
+            return true;
+        }
+        fade.last_sent = now;
+        set_playback_gain(
+            app,
+            fade.channel,
+            fade.from + (fade.to - fade.from) * progress,
+        );
+        if progress < 1.0 {
+            return true;
+        }
+        if let Some(restore) = fade.stop_at_end {
+            app.udp_client
+                .send_msg(Request::ControlAction(ControlAction::RunEvent(
+                    EventDescription::PlaybackStopEvent {
+                        channel_idx: fade.channel as u16,
+                    },
+                )));
+            set_playback_gain(app, fade.channel, restore);
+        }
+        false
+    });
+    app.local_memory.playback.fades.append(&mut fades);
 }
 
 pub fn stop_all(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    app.local_memory.playback.waiting.clear();
+    for fade in std::mem::take(&mut app.local_memory.playback.fades) {
+        set_playback_gain(app, fade.channel, fade.stop_at_end.unwrap_or(fade.to));
+    }
     for i in 0..30 {
         app.udp_client
             .send_msg(Request::ControlAction(ControlAction::RunEvent(
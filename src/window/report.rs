@@ -0,0 +1,581 @@
+use crate::{
+    app::ClicksMonitorApp,
+    window::performance::{save, timestamped},
+};
+use chrono::{DateTime, Utc};
+use common::{
+    cue::Cue,
+    mem::time::format_hms,
+    protocol::message::{LargeMessage, Message, SmallMessage},
+};
+use egui::{Grid, RichText, Widget};
+use std::fmt::Write;
+
+/// Something that happened during a performance, as seen by this monitor.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub enum ShowEventKind {
+    CueLoaded {
+        cue_idx: u8,
+        ident: String,
+        name: String,
+        /// Sum of the cue's beat lengths in µs.
+        nominal_us: u64,
+    },
+    TransportStart,
+    TransportStop,
+    Jump {
+        from: u16,
+        to: u16,
+    },
+    PlayrateChanged {
+        percent: u16,
+    },
+    ActStart {
+        label: String,
+    },
+    ActEnd,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ShowLogEntry {
+    /// Unix time in ms.
+    pub time: i64,
+    pub beat_idx: u16,
+    pub kind: ShowEventKind,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ReportWindowMemory {
+    pub recording: bool,
+    pub log: Vec<ShowLogEntry>,
+    pub act_label: String,
+    pub export_path: String,
+    #[serde(skip)]
+    export_result: Option<Result<String, String>>,
+}
+
+impl Default for ReportWindowMemory {
+    fn default() -> Self {
+        Self {
+            recording: false,
+            log: vec![],
+            act_label: "Act 1".to_string(),
+            export_path: "show-report".to_string(),
+            export_result: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ActSummary {
+    pub label: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct CueSummary {
+    pub cue_idx: u8,
+    pub ident: String,
+    pub name: String,
+    pub loaded: i64,
+    pub nominal_ms: i64,
+    /// Time the transport was running while the cue was loaded.
+    pub running_ms: i64,
+    pub jumps: usize,
+    /// Time-weighted mean playrate while running, in percent.
+    pub mean_playrate: f32,
+}
+
+impl CueSummary {
+    pub fn deviation_ms(&self) -> i64 {
+        self.running_ms - self.nominal_ms
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ShowReport<'a> {
+    acts: Vec<ActSummary>,
+    cues: Vec<CueSummary>,
+    log: &'a [ShowLogEntry],
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// Record show events from an incoming message. Must be called before the message is applied
+/// to the app status, since events are detected as changes from the previous state.
+pub fn record(app: &mut ClicksMonitorApp, msg: &Message) {
+    if !app.local_memory.report.recording {
+        return;
+    }
+    let beat_idx = app.status.beat_state().beat_idx;
+    let mut events = vec![];
+    match msg {
+        Message::Small(SmallMessage::TransportData(transport)) => {
+            if transport.running != app.status.transport.running {
+                events.push(if transport.running {
+                    ShowEventKind::TransportStart
+                } else {
+                    ShowEventKind::TransportStop
+                });
+            }
+            if transport.playrate_percent != app.status.transport.playrate_percent {
+                events.push(ShowEventKind::PlayrateChanged {
+                    percent: transport.playrate_percent,
+                });
+            }
+        }
+        Message::Small(SmallMessage::BeatData(beat)) => {
+            let from = beat_idx;
+            let to = beat.beat_idx;
+            if app.status.transport.running
+                && from < u16::MAX / 2
+                && to < u16::MAX / 2
+                && to != from
+                && to != from + 1
+            {
+                events.push(ShowEventKind::Jump { from, to });
+            }
+        }
+        Message::Large(LargeMessage::CueData(cue)) => {
+            if cue.cue_idx != app.status.cue.cue_idx
+                || cue.cue.metadata != app.status.cue.cue.metadata
+            {
+                events.push(cue_loaded(cue.cue_idx, &cue.cue));
+            }
+        }
+        _ => {}
+    }
+    for kind in events {
+        push(app, beat_idx, kind);
+    }
+}
+
+fn cue_loaded(cue_idx: u8, cue: &Cue) -> ShowEventKind {
+    ShowEventKind::CueLoaded {
+        cue_idx,
+        ident: cue.metadata.human_ident.str().to_string(),
+        name: cue.metadata.name.str().to_string(),
+        nominal_us: cue.beats.iter().map(|b| b.length as u64).sum(),
+    }
+}
+
+/// Start recording, logging the loaded cue and a running transport as if they had just
+/// happened, so a cue that is already playing is part of the report.
+fn start_recording(app: &mut ClicksMonitorApp) {
+    app.local_memory.report.recording = true;
+    let beat_idx = app.status.beat_state().beat_idx;
+    push(
+        app,
+        beat_idx,
+        cue_loaded(app.status.cue.cue_idx, &app.status.cue.cue),
+    );
+    if app.status.transport.running {
+        push(app, beat_idx, ShowEventKind::TransportStart);
+    }
+}
+
+fn push(app: &mut ClicksMonitorApp, beat_idx: u16, kind: ShowEventKind) {
+    app.local_memory.report.log.push(ShowLogEntry {
+        time: Utc::now().timestamp_millis(),
+        beat_idx,
+        kind,
+    });
+}
+
+/// Acts are the spans between act start markers and the following act end, next act start,
+/// or the last recorded event.
+pub fn acts(log: &[ShowLogEntry]) -> Vec<ActSummary> {
+    let end_of_log = log.last().map_or(0, |e| e.time);
+    let mut acts: Vec<ActSummary> = vec![];
+    let mut open = false;
+    for entry in log {
+        match &entry.kind {
+            ShowEventKind::ActStart { label } => {
+                if let Some(act) = acts.last_mut().filter(|_| open) {
+                    act.end = entry.time;
+                }
+                acts.push(ActSummary {
+                    label: label.clone(),
+                    start: entry.time,
+                    end: end_of_log,
+                });
+                open = true;
+            }
+            ShowEventKind::ActEnd => {
+                if let Some(act) = acts.last_mut().filter(|_| open) {
+                    act.end = entry.time;
+                }
+                open = false;
+            }
+            _ => {}
+        }
+    }
+    acts
+}
+
+/// Where a report ends: now while recording, otherwise at the last recorded event.
+fn log_end(memory: &ReportWindowMemory) -> i64 {
+    if memory.recording {
+        Utc::now().timestamp_millis()
+    } else {
+        memory.log.last().map_or(0, |e| e.time)
+    }
+}
+
+/// Per-cue running time and playrate, from one cue load to the next. A cue still running at
+/// the end of the log runs until `end`.
+pub fn cues(log: &[ShowLogEntry], end: i64) -> Vec<CueSummary> {
+    let mut cues: Vec<CueSummary> = vec![];
+    let mut running = false;
+    let mut playrate = 100.0;
+    let mut playrate_ms = 0.0;
+    let mut last_time = log.first().map_or(0, |e| e.time);
+    for entry in log {
+        if let Some(cue) = cues.last_mut()
+            && running
+        {
+            let ms = entry.time - last_time;
+            cue.running_ms += ms;
+            playrate_ms += playrate * ms as f32;
+            cue.mean_playrate = playrate_ms / cue.running_ms.max(1) as f32;
+        }
+        last_time = entry.time;
+        match &entry.kind {
+            ShowEventKind::CueLoaded {
+                cue_idx,
+                ident,
+                name,
+                nominal_us,
+            } => {
+                playrate_ms = 0.0;
+                cues.push(CueSummary {
+                    cue_idx: *cue_idx,
+                    ident: ident.clone(),
+                    name: name.clone(),
+                    loaded: entry.time,
+                    nominal_ms: (*nominal_us / 1000) as i64,
+                    running_ms: 0,
+                    jumps: 0,
+                    mean_playrate: playrate,
+                });
+            }
+            ShowEventKind::TransportStart => running = true,
+            ShowEventKind::TransportStop => running = false,
+            ShowEventKind::PlayrateChanged { percent } => playrate = *percent as f32,
+            ShowEventKind::Jump { .. } => {
+                if let Some(cue) = cues.last_mut() {
+                    cue.jumps += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(cue) = cues.last_mut()
+        && running
+    {
+        let ms = (end - last_time).max(0);
+        cue.running_ms += ms;
+        playrate_ms += playrate * ms as f32;
+        cue.mean_playrate = playrate_ms / cue.running_ms.max(1) as f32;
+    }
+    cues
+}
+
+/// Keep text from splitting a Markdown table cell.
+fn escape_table(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn timestamp(time: i64) -> String {
+    DateTime::from_timestamp_millis(time)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+fn format_ms(ms: i64) -> String {
+    format!(
+        "{}{}.{:01}",
+        if ms < 0 { "-" } else { "" },
+        format_hms(ms.unsigned_abs() / 1000).str(),
+        ms.unsigned_abs() % 1000 / 100
+    )
+}
+
+fn describe(kind: &ShowEventKind) -> String {
+    match kind {
+        ShowEventKind::CueLoaded { ident, name, .. } => format!("Cue loaded: {} {}", ident, name),
+        ShowEventKind::TransportStart => "Transport start".to_string(),
+        ShowEventKind::TransportStop => "Transport stop".to_string(),
+        ShowEventKind::Jump { from, to } => format!("Jump from beat {} to {}", from, to),
+        ShowEventKind::PlayrateChanged { percent } => format!("Playrate {}%", percent),
+        ShowEventKind::ActStart { label } => format!("{} start", label),
+        ShowEventKind::ActEnd => "Act end".to_string(),
+    }
+}
+
+fn export(memory: &ReportWindowMemory, format: ExportFormat) -> String {
+    let log = &memory.log;
+    let acts = acts(log);
+    let cues = cues(log, log_end(memory));
+    let mut out = String::new();
+    match format {
+        ExportFormat::Json => {
+            out = serde_json::to_string_pretty(&ShowReport { acts, cues, log }).unwrap_or_default()
+        }
+        ExportFormat::Csv => {
+            let _ = writeln!(
+                out,
+                "cue_idx,ident,name,loaded,nominal_ms,running_ms,deviation_ms,mean_playrate,jumps"
+            );
+            for cue in &cues {
+                let _ = writeln!(
+                    out,
+                    "{},\"{}\",\"{}\",{},{},{},{},{:.1},{}",
+                    cue.cue_idx,
+                    cue.ident.replace('"', "\"\""),
+                    cue.name.replace('"', "\"\""),
+                    timestamp(cue.loaded),
+                    cue.nominal_ms,
+                    cue.running_ms,
+                    cue.deviation_ms(),
+                    cue.mean_playrate,
+                    cue.jumps
+                );
+            }
+        }
+        ExportFormat::Markdown => {
+            let _ = writeln!(out, "# Show report\n");
+            let _ = writeln!(out, "## Acts\n");
+            let _ = writeln!(out, "| Act | Start | End | Length |");
+            let _ = writeln!(out, "|---|---|---|---|");
+            for act in &acts {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} |",
+                    escape_table(&act.label),
+                    timestamp(act.start),
+                    timestamp(act.end),
+                    format_ms(act.end - act.start)
+                );
+            }
+            let _ = writeln!(out, "\n## Cues\n");
+            let _ = writeln!(
+                out,
+                "| # | Cue | Loaded | Nominal | Running | Deviation | Playrate | Jumps |"
+            );
+            let _ = writeln!(out, "|---|---|---|---|---|---|---|---|");
+            for cue in &cues {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} {} | {} | {} | {} | {} | {:.1}% | {} |",
+                    cue.cue_idx,
+                    escape_table(&cue.ident),
+                    escape_table(&cue.name),
+                    timestamp(cue.loaded),
+                    format_ms(cue.nominal_ms),
+                    format_ms(cue.running_ms),
+                    format_ms(cue.deviation_ms()),
+                    cue.mean_playrate,
+                    cue.jumps
+                );
+            }
+            let _ = writeln!(out, "\n## Log\n");
+            for entry in log {
+                let _ = writeln!(
+                    out,
+                    "- {} (beat {}): {}",
+                    timestamp(entry.time),
+                    entry.beat_idx,
+                    describe(&entry.kind)
+                );
+            }
+        }
+    }
+    out
+}
+
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Show Report").heading());
+        if app.local_memory.report.recording {
+            if egui::Button::new("Stop recording")
+                .fill(app.theme.err_prim_wk)
+                .ui(ui)
+                .clicked()
+            {
+                // Close the running cue where the recording stopped
+                if app.status.transport.running {
+                    push(
+                        app,
+                        app.status.beat_state().beat_idx,
+                        ShowEventKind::TransportStop,
+                    );
+                }
+                app.local_memory.report.recording = false;
+            }
+            ui.colored_label(app.theme.err_prim, "● REC");
+        } else if egui::Button::new("Start recording")
+            .fill(app.theme.cued_prim)
+            .ui(ui)
+            .clicked()
+        {
+            start_recording(app);
+        }
+        let memory = &mut app.local_memory.report;
+        if ui
+            .add_enabled(
+                !memory.recording && !memory.log.is_empty(),
+                egui::Button::new("Clear"),
+            )
+            .on_disabled_hover_text("Stop recording to clear the report")
+            .clicked()
+        {
+            memory.log.clear();
+        }
+
+        ui.separator();
+        let marker = ui
+            .add_enabled_ui(memory.recording, |ui| {
+                egui::TextEdit::singleline(&mut memory.act_label)
+                    .desired_width(100.0)
+                    .ui(ui);
+                let start = ui.button("Start act").clicked();
+                let end = ui.button("End act").clicked();
+                if start {
+                    Some(ShowEventKind::ActStart {
+                        label: memory.act_label.clone(),
+                    })
+                } else if end {
+                    Some(ShowEventKind::ActEnd)
+                } else {
+                    None
+                }
+            })
+            .inner;
+        if let Some(kind) = marker {
+            push(app, app.status.beat_state().beat_idx, kind);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Export to");
+        egui::TextEdit::singleline(&mut app.local_memory.report.export_path)
+            .desired_width(300.0)
+            .ui(ui)
+            .on_hover_text("The export time is added to the file name");
+        for (name, format) in [
+            ("CSV", ExportFormat::Csv),
+            ("JSON", ExportFormat::Json),
+            ("Markdown", ExportFormat::Markdown),
+        ] {
+            ui.menu_button(name, |ui| {
+                let memory = &mut app.local_memory.report;
+                if ui.button("Save to file").clicked() {
+                    let path = format!(
+                        "{}.{}",
+                        timestamped(&memory.export_path),
+                        format.extension()
+                    );
+                    memory.export_result = Some(
+                        save(&path, &export(memory, format)).map(|path| format!("Saved {}", path)),
+                    );
+                    ui.close_menu();
+                }
+                if ui.button("Copy to clipboard").clicked() {
+                    ui.ctx().copy_text(export(memory, format));
+                    memory.export_result = Some(Ok(format!("Copied {} report", name)));
+                    ui.close_menu();
+                }
+            });
+        }
+        match &app.local_memory.report.export_result {
+            Some(Ok(text)) => ui.colored_label(app.theme.active_prim, text),
+            Some(Err(text)) => ui.colored_label(app.theme.err_prim, text),
+            None => ui.label(""),
+        };
+    });
+    ui.separator();
+
+    let log = &app.local_memory.report.log;
+    let end = log_end(&app.local_memory.report);
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.label(RichText::new("Acts").strong());
+        Grid::new("report-acts").striped(true).show(ui, |ui| {
+            ui.label("Act");
+            ui.label("Start");
+            ui.label("Length");
+            ui.end_row();
+            for act in acts(log) {
+                ui.label(act.label);
+                ui.label(format_hms(act.start as u64 / 1000).str());
+                ui.label(format_ms(act.end - act.start));
+                ui.end_row();
+            }
+        });
+
+        ui.add_space(8.0);
+        ui.label(RichText::new("Cues").strong());
+        Grid::new("report-cues").striped(true).show(ui, |ui| {
+            for title in [
+                "#",
+                "Cue",
+                "Loaded",
+                "Nominal",
+                "Running",
+                "Deviation",
+                "Playrate",
+                "Jumps",
+            ] {
+                ui.label(title);
+            }
+            ui.end_row();
+            for cue in cues(log, end) {
+                ui.label(format!("{:0>3}", cue.cue_idx));
+                ui.label(format!("{} {}", cue.ident, cue.name));
+                ui.label(format_hms(cue.loaded as u64 / 1000).str());
+                ui.label(format_ms(cue.nominal_ms));
+                ui.label(format_ms(cue.running_ms));
+                ui.colored_label(
+                    if cue.deviation_ms().abs() > 5000 {
+                        app.theme.warn_prim
+                    } else {
+                        app.theme.neutral_prim
+                    },
+                    format_ms(cue.deviation_ms()),
+                );
+                ui.label(format!("{:.1}%", cue.mean_playrate));
+                ui.label(cue.jumps.to_string());
+                ui.end_row();
+            }
+        });
+
+        ui.add_space(8.0);
+        egui::CollapsingHeader::new(format!("Log ({} events)", log.len())).show(ui, |ui| {
+            for entry in log {
+                ui.label(format!(
+                    "{}  {:>5}  {}",
+                    format_hms(entry.time as u64 / 1000).str(),
+                    entry.beat_idx,
+                    describe(&entry.kind)
+                ));
+            }
+        });
+    });
+}
@@ -1,37 +1,130 @@
+use std::time::{Duration, Instant};
+
 use crate::app::ClicksMonitorApp;
-use common::{local::config::SystemConfigurationChange, protocol::request::Request};
+use common::{
+    local::{
+        config::{LogContext, LogKind, SystemConfigurationChange},
+        status::{AudioDevice, JACKStatus},
+    },
+    mem::time::format_hms,
+    protocol::request::Request,
+};
 use egui::Widget;
 
+const FALLBACK_SAMPLE_RATES: [u32; 2] = [44100, 48000];
+const FALLBACK_NUM_PERIODS: [u32; 3] = [2, 3, 4];
+const PERIOD_SIZES: [u32; 6] = [128, 256, 512, 1024, 2048, 4096];
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lifecycle of the audio processor as seen from the monitor. Launching moves to `Starting`,
+/// and the next `JACKStateChanged` decides whether it came up, or the timeout if none arrives.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum AudioProcessorState {
+    #[default]
+    Stopped,
+    Starting(Instant),
+    Running,
+    Stopping(Instant),
+    Failed(String),
+}
+
+impl AudioProcessorState {
+    pub fn on_jack_status(&mut self, status: &JACKStatus) {
+        *self = match (&self, status.running) {
+            (_, true) => Self::Running,
+            (Self::Starting(_), false) => {
+                Self::Failed("Audio processor reported it is not running.".to_string())
+            }
+            // Keep the reason of the failure
+            (Self::Failed(_), false) => return,
+            (_, false) => Self::Stopped,
+        };
+    }
+
+    /// Time out a launch the core never confirmed.
+    fn check_timeout(&mut self, last_error: Option<String>) {
+        match self {
+            Self::Starting(time) if time.elapsed() > START_TIMEOUT => {
+                *self = Self::Failed(
+                    last_error
+                        .unwrap_or_else(|| "Audio processor did not start in time.".to_string()),
+                );
+            }
+            Self::Stopping(time) if time.elapsed() > START_TIMEOUT => {
+                *self = Self::Failed("Audio processor did not stop in time.".to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn selected_device(app: &ClicksMonitorApp) -> Option<AudioDevice> {
+    app.status
+        .jack_status
+        .available_devices
+        .iter()
+        .flatten()
+        .find(|d| d.id == app.system_config.audio.server.device_id)
+        .copied()
+}
+
+fn last_jack_error(app: &ClicksMonitorApp) -> Option<(u64, String)> {
+    app.log_entries
+        .iter()
+        .rev()
+        .find(|entry| {
+            entry.kind.contains(LogKind::Error)
+                && entry.context.contains(LogContext::AudioProcessor)
+        })
+        .map(|entry| (entry.time, entry.message.clone()))
+}
+
+/// Called every frame, so a launch times out whether or not this page is open.
+pub fn update(app: &mut ClicksMonitorApp) {
+    let last_error = last_jack_error(app).map(|(_, message)| message);
+    app.audio_processor.check_timeout(last_error);
+}
+
 pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let last_error = last_jack_error(app);
+
+    let device = selected_device(app);
+    let sample_rates: Vec<u32> = match device {
+        Some(device) if device.sample_rates.iter().any(Option::is_some) => {
+            device.sample_rates.iter().flatten().copied().collect()
+        }
+        _ => FALLBACK_SAMPLE_RATES.to_vec(),
+    };
+    let num_periods: Vec<u32> = match device {
+        Some(device) if device.num_periods.iter().any(Option::is_some) => {
+            device.num_periods.iter().flatten().copied().collect()
+        }
+        _ => FALLBACK_NUM_PERIODS.to_vec(),
+    };
+
     egui::Grid::new("audio-system-settings")
         .num_columns(2)
         .show(ui, |ui| {
-            if app.status.jack_status.running {
+            if app.status.jack_status.running || !app.local_memory.security.allow_interaction {
                 ui.disable();
             }
             ui.label(egui::RichText::new("Audio").underline());
             ui.end_row();
 
             ui.label("Device name");
-            let mut devices = app.status.jack_status.available_devices.iter();
             egui::ComboBox::new("device-selector", "")
-                .selected_text(
-                    match devices.position(|d| {
-                        d.unwrap_or_default().id == app.system_config.audio.server.device_id
-                    }) {
-                        Some(idx) => app.status.jack_status.available_devices[idx]
-                            .map_or("".to_string(), |v| v.name.str().to_string()),
-                        None => "No audio device selected".to_string(),
-                    },
-                )
+                .selected_text(match device {
+                    Some(device) => device.name.str().to_string(),
+                    None => "No audio device selected".to_string(),
+                })
                 .truncate()
                 .width(400.0)
                 .show_ui(ui, |ui| {
-                    for device in app.status.jack_status.available_devices {
+                    for device in app.status.jack_status.available_devices.iter().flatten() {
                         ui.selectable_value(
                             &mut app.system_config.audio.server.device_id,
-                            device.unwrap_or_default().id,
-                            device.unwrap_or_default().name.str(),
+                            device.id,
+                            device.name.str(),
                         );
                     }
                 });
@@ -39,47 +132,116 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
 
             ui.label("Sample rate");
             ui.horizontal(|ui| {
-                for (rate, label) in [
-                    (44100, "44.1kHz"),
-                    (48000, "48kHz"),
-                    //(96000, "96kHz"),
-                    //(192000, "192kHz"),
-                ] {
-                    if ui
-                        .selectable_label(app.system_config.audio.server.sample_rate == rate, label)
-                        .clicked()
-                    {
-                        app.system_config.audio.server.sample_rate = rate;
-                    }
+                for rate in sample_rates {
+                    ui.selectable_value(
+                        &mut app.system_config.audio.server.sample_rate,
+                        rate,
+                        format!("{}kHz", rate as f32 / 1000.0),
+                    );
                 }
             });
             ui.end_row();
 
             ui.label("Period size (samples)");
             ui.horizontal(|ui| {
-                for size in [128, 256, 512, 1024, 2048, 4096] {
-                    if ui
-                        .selectable_label(
-                            app.system_config.audio.server.period_size == size,
-                            size.to_string(),
-                        )
-                        .clicked()
-                    {
-                        app.system_config.audio.server.period_size = size;
-                    }
+                for size in PERIOD_SIZES {
+                    ui.selectable_value(
+                        &mut app.system_config.audio.server.period_size,
+                        size,
+                        size.to_string(),
+                    );
+                }
+            });
+            ui.end_row();
+
+            ui.label("Periods");
+            ui.horizontal(|ui| {
+                for periods in num_periods {
+                    ui.selectable_value(
+                        &mut app.system_config.audio.server.num_periods,
+                        periods,
+                        periods.to_string(),
+                    );
                 }
             });
             ui.end_row();
 
-            if egui::Button::new("Launch Audio Processor")
-                .fill(app.theme.cued_prim)
-                .ui(ui)
+            let server = app.system_config.audio.server;
+            ui.label("Expected latency");
+            if server.sample_rate > 0 {
+                let period_ms = server.period_size as f32 * 1000.0 / server.sample_rate as f32;
+                ui.label(format!(
+                    "{:.1} ms ({} x {:.1} ms periods)",
+                    period_ms * server.num_periods as f32,
+                    server.num_periods,
+                    period_ms
+                ));
+            } else {
+                ui.label("-");
+            }
+            ui.end_row();
+        });
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        if !app.local_memory.security.allow_interaction {
+            ui.disable();
+        }
+        let busy = matches!(
+            app.audio_processor,
+            AudioProcessorState::Starting(_) | AudioProcessorState::Stopping(_)
+        );
+        if app.status.jack_status.running {
+            if ui
+                .add_enabled(
+                    !busy,
+                    egui::Button::new("Stop Audio Processor").fill(app.theme.err_prim_wk),
+                )
                 .clicked()
             {
-                app.udp_client.send_msg(Request::ChangeConfiguration(
-                    SystemConfigurationChange::ChangeAudioConfiguration(app.system_config.audio),
-                ));
-                app.udp_client.send_msg(Request::Initialize);
+                app.udp_client.send_msg(Request::Deinitialize);
+                app.audio_processor = AudioProcessorState::Stopping(Instant::now());
             }
+        } else if ui
+            .add_enabled(
+                !busy,
+                egui::Button::new("Launch Audio Processor").fill(app.theme.cued_prim),
+            )
+            .clicked()
+        {
+            app.udp_client.send_msg(Request::ChangeConfiguration(
+                SystemConfigurationChange::ChangeAudioConfiguration(app.system_config.audio),
+            ));
+            app.udp_client.send_msg(Request::Initialize);
+            app.audio_processor = AudioProcessorState::Starting(Instant::now());
+        }
+
+        match &app.audio_processor {
+            AudioProcessorState::Stopped => ui.label("Stopped"),
+            AudioProcessorState::Starting(time) => ui.colored_label(
+                app.theme.warn_prim,
+                format!("Starting... ({}s)", time.elapsed().as_secs()),
+            ),
+            AudioProcessorState::Running => ui.colored_label(app.theme.active_prim, "Running"),
+            AudioProcessorState::Stopping(_) => {
+                ui.colored_label(app.theme.warn_prim, "Stopping...")
+            }
+            AudioProcessorState::Failed(reason) => {
+                ui.colored_label(app.theme.err_prim, format!("Failed: {}", reason))
+            }
+        };
+    });
+
+    if let Some((time, message)) = last_error {
+        ui.horizontal(|ui| {
+            ui.label("Last JACK error:");
+            egui::Label::new(
+                egui::RichText::new(format!("[{}] {}", format_hms(time / 1000).str(), message))
+                    .color(app.theme.err_prim),
+            )
+            .wrap()
+            .ui(ui);
         });
+    }
 }
@@ -0,0 +1,179 @@
+use crate::{app::ClicksMonitorApp, timing, window::cue::rehearsal_marks};
+use common::protocol::request::{ControlAction, Request};
+use egui::{Align2, CornerRadius, FontId, Rect, RichText, Sense, Stroke, Vec2, vec2};
+
+const BLOCK_HEIGHT: f32 = 96.0;
+const MIN_BLOCK_WIDTH: f32 = 48.0;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ShowWindowMemory {
+    pub px_per_minute: f32,
+    pub follow_playhead: bool,
+}
+
+impl Default for ShowWindowMemory {
+    fn default() -> Self {
+        Self {
+            px_per_minute: 120.0,
+            follow_playhead: true,
+        }
+    }
+}
+
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let cues = app.status.show.cues.clone();
+    let current = app.status.cue.cue_idx as usize;
+    let beat_idx = app.status.beat_state().beat_idx as usize;
+    let lengths: Vec<u64> = cues.iter().map(|c| timing::cue_length(c, 100)).collect();
+    let show_length: u64 = lengths.iter().sum();
+    let show_position: u64 = lengths.iter().take(current).sum::<u64>()
+        + cues
+            .get(current)
+            .map_or(0, |cue| timing::beat_offset(cue, beat_idx));
+
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(app.status.show.metadata.name.str()).heading());
+        ui.separator();
+        ui.label(
+            RichText::new(format!(
+                "{} / {}",
+                timing::format_us(show_position),
+                timing::format_us(show_length)
+            ))
+            .monospace(),
+        );
+        ui.separator();
+        ui.add(
+            egui::Slider::new(&mut app.local_memory.show.px_per_minute, 20.0..=1200.0)
+                .logarithmic(true)
+                .text("px/min"),
+        );
+        ui.checkbox(&mut app.local_memory.show.follow_playhead, "Follow");
+    });
+
+    if cues.is_empty() {
+        ui.label("No show loaded.");
+        return;
+    }
+
+    let scale = app.local_memory.show.px_per_minute / 60_000_000.0;
+    let mut clicked = None;
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        let widths: Vec<f32> = lengths
+            .iter()
+            .map(|l| (*l as f32 * scale).max(MIN_BLOCK_WIDTH))
+            .collect();
+        let (rect, resp) = ui.allocate_exact_size(
+            vec2(widths.iter().sum(), BLOCK_HEIGHT + 24.0),
+            Sense::click(),
+        );
+        let p = ui.painter();
+        let mut x = rect.left();
+        for (i, cue) in cues.iter().enumerate() {
+            let block =
+                Rect::from_min_size(egui::pos2(x, rect.top()), vec2(widths[i], BLOCK_HEIGHT));
+            x += widths[i];
+            let hovered = resp.hovered() && resp.hover_pos().is_some_and(|p| block.contains(p));
+            p.rect(
+                block.shrink(1.0),
+                CornerRadius::same(2),
+                if i == current {
+                    app.theme.cued_prim.gamma_multiply(0.3)
+                } else {
+                    app.theme.base_wk
+                },
+                Stroke::new(
+                    if hovered { 2.0 } else { 1.0 },
+                    if i == current {
+                        app.theme.cued_prim
+                    } else {
+                        app.theme.neutral_prim
+                    },
+                ),
+                egui::StrokeKind::Inside,
+            );
+            let text_clip = p.with_clip_rect(block.shrink(2.0));
+            text_clip.text(
+                block.left_top() + vec2(4.0, 4.0),
+                Align2::LEFT_TOP,
+                cue.metadata.human_ident.str(),
+                FontId::monospace(14.0),
+                app.theme.neutral_prim,
+            );
+            text_clip.text(
+                block.left_top() + vec2(4.0, 22.0),
+                Align2::LEFT_TOP,
+                cue.metadata.name.str(),
+                FontId::proportional(14.0),
+                app.theme.neutral_prim,
+            );
+            text_clip.text(
+                block.left_bottom() + vec2(4.0, -4.0),
+                Align2::LEFT_BOTTOM,
+                timing::format_us(lengths[i]),
+                FontId::monospace(12.0),
+                app.theme.neutral_prim,
+            );
+
+            // Rehearsal marks, placed by time unless the block has been widened to its minimum
+            let block_scale = widths[i] / lengths[i].max(1) as f32;
+            for (location, label) in rehearsal_marks(cue) {
+                let mark_x =
+                    block.left() + timing::beat_offset(cue, location as usize) as f32 * block_scale;
+                p.vline(
+                    mark_x,
+                    block.bottom() - 16.0..=block.bottom() + 4.0,
+                    Stroke::new(1.0, app.theme.warn_prim),
+                );
+                p.text(
+                    egui::pos2(mark_x + 2.0, block.bottom() + 4.0),
+                    Align2::LEFT_TOP,
+                    label,
+                    FontId::proportional(12.0),
+                    app.theme.warn_prim,
+                );
+            }
+
+            if i == current {
+                let playhead_x =
+                    block.left() + timing::beat_offset(cue, beat_idx) as f32 * block_scale;
+                p.vline(
+                    playhead_x,
+                    block.y_range(),
+                    Stroke::new(2.0, app.theme.active_prim),
+                );
+                if app.local_memory.show.follow_playhead && app.status.transport.running {
+                    ui.scroll_to_rect(
+                        Rect::from_center_size(
+                            egui::pos2(playhead_x, block.center().y),
+                            Vec2::splat(1.0),
+                        ),
+                        Some(egui::Align::Center),
+                    );
+                }
+            }
+
+            if hovered {
+                resp.clone().on_hover_text(format!(
+                    "{} {}\n{}\nClick to load",
+                    cue.metadata.human_ident.str(),
+                    cue.metadata.name.str(),
+                    timing::format_us(lengths[i])
+                ));
+                if resp.clicked() {
+                    clicked = Some(i);
+                }
+            }
+        }
+    });
+
+    if let Some(i) = clicked
+        && app.local_memory.security.allow_interaction
+    {
+        app.udp_client
+            .send_msg(Request::ControlAction(ControlAction::LoadCueByIndex(
+                i as u8,
+            )));
+    }
+}
@@ -1,10 +1,21 @@
-use crate::app::ClicksMonitorApp;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    app::ClicksMonitorApp,
+    history::{self, MixerChange},
+    widget::meter::vertical_meter,
+};
 use common::{
     local::{
-        config::{ChannelAssignment, ChannelConfiguration, SystemConfigurationChange},
-        status::AudioSourceState,
+        config::{
+            ChannelAssignment, ChannelConfiguration, SystemConfiguration, SystemConfigurationChange,
+        },
+        status::{AudioSourceState, NO_CLIP},
     },
-    mem::time::format_hms,
+    mem::{str::StaticString, time::format_hms},
     protocol::request::{ControlAction, Request},
 };
 use egui::{
@@ -147,11 +158,7 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                                     })
                                     .fill(if playing {
                                         app.theme.active_prim
-                                    } else if *clip < 2048 {
-                                        // FIXME: magic number for "usize::MAX
-                                        // of the core machine, which we can't
-                                        // guarantee is the same as on this
-                                        // machine
+                                    } else if *clip != NO_CLIP {
                                         app.theme.cued_prim
                                     } else {
                                         Color32::TRANSPARENT
@@ -160,7 +167,7 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                                         ui.horizontal_centered(|ui| {
                                             ui.set_width(64.0);
                                             ui.set_height(16.0);
-                                            ui.label(if *clip < 2048 {
+                                            ui.label(if *clip != NO_CLIP {
                                                 clip.to_string()
                                             } else {
                                                 "".to_string()
@@ -193,56 +200,264 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
         });
 }
 
-pub fn configuration_window(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
-    if egui::Button::new("Apply").ui(ui).clicked() {
-        for i in 0..32 {
-            app.udp_client.send_msg(Request::ChangeConfiguration(
-                SystemConfigurationChange::ChangeChannelConfiguration(
-                    i as u8,
-                    app.system_config.channels[i],
-                ),
+const NAME_MAX_LEN: usize = 16;
+const DESCRIPTION_MAX_LEN: usize = 32;
+const GAIN_RANGE: std::ops::RangeInclusive<f32> = -48.0..=12.0;
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(3);
+/// Channels 0 and 1 are the beat and time sources, which are not configurable.
+const FIRST_EDITABLE_CHANNEL: usize = 2;
+
+/// Editable copy of one channel configuration. Strings are kept as owned buffers so the text
+/// fields can edit them, and are only converted back when the change is sent.
+#[derive(Clone, PartialEq)]
+pub struct ChannelDraft {
+    name: String,
+    description: String,
+    channel_assignment: ChannelAssignment,
+    gain: f32,
+}
+
+impl ChannelDraft {
+    fn from_config(conf: &ChannelConfiguration) -> Self {
+        Self {
+            name: conf.name.str().to_string(),
+            description: conf.description.str().to_string(),
+            channel_assignment: conf.channel_assignment,
+            gain: conf.gain,
+        }
+    }
+
+    fn to_config(&self, base: ChannelConfiguration) -> ChannelConfiguration {
+        let mut conf = base;
+        conf.name = StaticString::new(self.name.trim());
+        conf.description = StaticString::new(self.description.trim());
+        conf.channel_assignment = self.channel_assignment;
+        conf.gain = self.gain;
+        conf
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChannelChangeState {
+    Pending(Instant),
+    Confirmed,
+    Reverted,
+}
+
+/// Channel setup page state. Tracks local drafts against the last configuration received from
+/// the core, and the state of each change sent to it.
+#[derive(Default)]
+pub struct ChannelEditor {
+    drafts: Vec<ChannelDraft>,
+    changes: HashMap<usize, (ChannelConfiguration, ChannelChangeState)>,
+}
+
+impl ChannelEditor {
+    fn sync(&mut self, config: &SystemConfiguration) {
+        if self.drafts.len() != config.channels.len() {
+            self.drafts = config
+                .channels
+                .iter()
+                .map(ChannelDraft::from_config)
+                .collect();
+        }
+
+        for (idx, (sent, state)) in self.changes.iter_mut() {
+            if let ChannelChangeState::Pending(time) = state
+                && time.elapsed() > CONFIRM_TIMEOUT
+            {
+                *state = if config.channels[*idx] == *sent {
+                    ChannelChangeState::Confirmed
+                } else {
+                    self.drafts[*idx] = ChannelDraft::from_config(&config.channels[*idx]);
+                    ChannelChangeState::Reverted
+                };
+            }
+        }
+    }
+
+    /// Called before the app replaces its configuration with `new`. Confirms pending changes the
+    /// core echoed back and refreshes drafts that weren't edited locally. Each change is sent
+    /// separately, so a pending channel that doesn't match yet waits for its own echo, and is
+    /// only reverted by [`Self::sync`] once `CONFIRM_TIMEOUT` has passed.
+    pub fn on_configuration_changed(
+        &mut self,
+        old: &SystemConfiguration,
+        new: &SystemConfiguration,
+    ) {
+        if self.drafts.len() != new.channels.len() {
+            self.drafts = new.channels.iter().map(ChannelDraft::from_config).collect();
+            return;
+        }
+        for i in 0..new.channels.len() {
+            match self.changes.get_mut(&i) {
+                Some((sent, state @ ChannelChangeState::Pending(_))) => {
+                    if new.channels[i] == *sent {
+                        *state = ChannelChangeState::Confirmed;
+                    }
+                }
+                _ => {
+                    if self.drafts[i] == ChannelDraft::from_config(&old.channels[i]) {
+                        self.drafts[i] = ChannelDraft::from_config(&new.channels[i]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_dirty(&self, idx: usize, config: &SystemConfiguration) -> bool {
+        self.drafts[idx] != ChannelDraft::from_config(&config.channels[idx])
+    }
+
+    fn is_pending(&self, idx: usize) -> bool {
+        matches!(
+            self.changes.get(&idx),
+            Some((_, ChannelChangeState::Pending(_)))
+        )
+    }
+
+    fn validate(&self, idx: usize) -> Result<(), String> {
+        let draft = &self.drafts[idx];
+        if draft.name.trim().is_empty() {
+            return Err("Name is required".to_string());
+        }
+        if draft.name.trim().chars().count() > NAME_MAX_LEN {
+            return Err(format!("Name is longer than {NAME_MAX_LEN} characters"));
+        }
+        if draft.description.trim().chars().count() > DESCRIPTION_MAX_LEN {
+            return Err(format!(
+                "Description is longer than {DESCRIPTION_MAX_LEN} characters"
             ));
         }
+        if !GAIN_RANGE.contains(&draft.gain) {
+            return Err(format!(
+                "Gain must be between {} and {} dB",
+                GAIN_RANGE.start(),
+                GAIN_RANGE.end()
+            ));
+        }
+        if draft.channel_assignment == ChannelAssignment::R
+            && idx
+                .checked_sub(1)
+                .filter(|prev| *prev >= FIRST_EDITABLE_CHANNEL)
+                .is_none_or(|prev| self.drafts[prev].channel_assignment != ChannelAssignment::L)
+        {
+            return Err("R channel must follow an L channel".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn configuration_window(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.label(RichText::new("Channels").heading());
+    if !app.udp_client.active {
+        ui.colored_label(app.theme.err_prim, MSG_NO_CONNECTION);
+        return;
+    }
+    if !app.local_memory.security.allow_interaction {
+        ui.disable();
     }
+
+    app.channel_editor.sync(&app.system_config);
+
+    // Channels waiting for the core to confirm are not sent again
+    let dirty: Vec<usize> = (FIRST_EDITABLE_CHANNEL..app.channel_editor.drafts.len())
+        .filter(|&i| {
+            app.channel_editor.is_dirty(i, &app.system_config) && !app.channel_editor.is_pending(i)
+        })
+        .collect();
+    let valid = dirty
+        .iter()
+        .all(|&i| app.channel_editor.validate(i).is_ok());
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(
+                !dirty.is_empty() && valid,
+                egui::Button::new(format!("Apply ({} changed)", dirty.len())),
+            )
+            .on_disabled_hover_text("Nothing to apply, or some channels have errors.")
+            .clicked()
+            && app.local_memory.security.allow_interaction
+        {
+            for &i in &dirty {
+                let conf = app.channel_editor.drafts[i].to_config(app.system_config.channels[i]);
+                app.udp_client.send_msg(Request::ChangeConfiguration(
+                    SystemConfigurationChange::ChangeChannelConfiguration(i as u8, conf),
+                ));
+                app.channel_editor
+                    .changes
+                    .insert(i, (conf, ChannelChangeState::Pending(Instant::now())));
+            }
+        }
+        if ui
+            .add_enabled(!dirty.is_empty(), egui::Button::new("Discard"))
+            .clicked()
+        {
+            for &i in &dirty {
+                app.channel_editor.drafts[i] =
+                    ChannelDraft::from_config(&app.system_config.channels[i]);
+            }
+        }
+    });
+
     egui::ScrollArea::vertical().show(ui, |ui| {
         ui.set_width(ui.available_width());
         egui::Grid::new("channels-grid")
-            .num_columns(5)
+            .num_columns(6)
+            .striped(true)
             .show(ui, |ui| {
                 ui.label("Src. #");
-                ui.label("Ch. #");
-                ui.label("Side");
                 ui.label("Channel Name");
                 ui.label("Description");
+                ui.label("Side");
+                ui.label("Default gain");
+                ui.label("State");
                 ui.end_row();
 
-                for (i, channel) in app.system_config.channels[2..].iter_mut().enumerate() {
-                    ui.label(format!("{:0>2}", i + 3));
-                    ui.label(format!("{:0>2}", i));
-                    egui::ComboBox::new(format!("channel-assignment-selector-{i}"), "")
-                        .selected_text(match channel.channel_assignment {
-                            ChannelAssignment::L => "L",
-                            ChannelAssignment::R => "R",
-                            ChannelAssignment::Mono => "Mono",
-                        })
-                        .show_ui(ui, |ui| {
-                            ui.selectable_value(
-                                &mut channel.channel_assignment,
-                                ChannelAssignment::L,
-                                "L",
-                            );
-                            ui.selectable_value(
-                                &mut channel.channel_assignment,
-                                ChannelAssignment::R,
-                                "R",
-                            );
-                            ui.selectable_value(
-                                &mut channel.channel_assignment,
-                                ChannelAssignment::Mono,
-                                "Mono",
-                            );
-                        });
-                    ui.text_edit_singleline(&mut channel.name.str());
+                for i in FIRST_EDITABLE_CHANNEL..app.channel_editor.drafts.len() {
+                    let dirty = app.channel_editor.is_dirty(i, &app.system_config);
+                    let validation = app.channel_editor.validate(i);
+                    let draft = &mut app.channel_editor.drafts[i];
+
+                    ui.label(RichText::new(format!("{:0>2}", i + 1)).monospace());
+                    egui::TextEdit::singleline(&mut draft.name)
+                        .char_limit(NAME_MAX_LEN)
+                        .desired_width(160.0)
+                        .ui(ui);
+                    egui::TextEdit::singleline(&mut draft.description)
+                        .char_limit(DESCRIPTION_MAX_LEN)
+                        .desired_width(320.0)
+                        .ui(ui);
+                    ui.horizontal(|ui| {
+                        for (val, label) in [
+                            (ChannelAssignment::L, "L"),
+                            (ChannelAssignment::Mono, "Mono"),
+                            (ChannelAssignment::R, "R"),
+                        ] {
+                            ui.selectable_value(&mut draft.channel_assignment, val, label);
+                        }
+                    });
+                    egui::DragValue::new(&mut draft.gain)
+                        .range(GAIN_RANGE)
+                        .speed(0.1)
+                        .custom_formatter(|val, _| format!("{:0>+2.1}dB", val))
+                        .ui(ui);
+
+                    match (validation, app.channel_editor.changes.get(&i)) {
+                        (_, Some((_, ChannelChangeState::Pending(_)))) => {
+                            ui.colored_label(app.theme.warn_prim, "Pending...")
+                        }
+                        (Err(err), _) => ui.colored_label(app.theme.err_prim, err),
+                        (Ok(()), _) if dirty => ui.colored_label(app.theme.warn_prim, "Modified"),
+                        (Ok(()), Some((_, ChannelChangeState::Confirmed))) => {
+                            ui.colored_label(app.theme.active_prim, "Confirmed")
+                        }
+                        (Ok(()), Some((_, ChannelChangeState::Reverted))) => {
+                            ui.colored_label(app.theme.err_prim, "Reverted (not confirmed by core)")
+                        }
+                        (Ok(()), None) => ui.label(""),
+                    };
                     ui.end_row();
                 }
             });
@@ -288,23 +503,37 @@ pub fn channel_strip(app: &mut ClicksMonitorApp, ui: &mut egui::Ui, idx: usize,
             let max_slider = 12.0f32;
             let min_slider = -48.0f32;
             let mut slider_value = app.sources_gains[idx];
-            if custom_volume_slider(
-                app,
-                ui,
-                width,
-                500.0,
-                &mut slider_value,
-                max_slider,
-                min_slider,
-            )
-            .drag_stopped()
-                && app.local_memory.security.allow_interaction
-            {
-                app.udp_client
-                    .send_msg(Request::ControlAction(ControlAction::SetChannelGain(
-                        idx as u8,
-                        app.sources_gains[idx],
-                    )));
+            let meter_width = 10.0;
+            let slider = ui
+                .horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 2.0;
+                    let slider = custom_volume_slider(
+                        app,
+                        ui,
+                        width - meter_width - 2.0,
+                        500.0,
+                        &mut slider_value,
+                        max_slider,
+                        min_slider,
+                    );
+                    vertical_meter(
+                        ui,
+                        &mut app.input_meters[idx],
+                        &app.theme,
+                        Vec2::new(meter_width, 500.0),
+                    );
+                    slider
+                })
+                .inner;
+            if slider.drag_started() {
+                let gain_before = app.sources_gains[idx];
+                ui.data_mut(|d| d.insert_temp(slider.id, gain_before));
+            }
+            if slider.drag_stopped() && app.local_memory.security.allow_interaction {
+                let gain_before = ui
+                    .data(|d| d.get_temp::<f32>(slider.id))
+                    .unwrap_or(app.sources_gains[idx]);
+                let mut channels = vec![idx];
                 if conf.channel_assignment == ChannelAssignment::L
                     && app
                         .system_config
@@ -312,11 +541,7 @@ pub fn channel_strip(app: &mut ClicksMonitorApp, ui: &mut egui::Ui, idx: usize,
                         .get(idx + 1)
                         .is_some_and(|c| c.channel_assignment == ChannelAssignment::R)
                 {
-                    app.udp_client
-                        .send_msg(Request::ControlAction(ControlAction::SetChannelGain(
-                            idx as u8 + 1,
-                            slider_value,
-                        )));
+                    channels.push(idx + 1);
                 } else if conf.channel_assignment == ChannelAssignment::R
                     && app
                         .system_config
@@ -324,12 +549,26 @@ pub fn channel_strip(app: &mut ClicksMonitorApp, ui: &mut egui::Ui, idx: usize,
                         .get(idx.saturating_sub(1))
                         .is_some_and(|c| c.channel_assignment == ChannelAssignment::L)
                 {
-                    app.udp_client
-                        .send_msg(Request::ControlAction(ControlAction::SetChannelGain(
-                            idx as u8 - 1,
-                            slider_value,
-                        )));
+                    channels.push(idx - 1);
+                }
+                app.local_memory.history.begin_group();
+                for channel in channels {
+                    let from = if channel == idx {
+                        gain_before
+                    } else {
+                        app.sources_gains[channel]
+                    };
+                    app.sources_gains[channel] = slider_value;
+                    history::submit(
+                        app,
+                        MixerChange::Gain {
+                            channel: channel as u8,
+                            from,
+                            to: slider_value,
+                        },
+                    );
                 }
+                app.local_memory.history.end_group();
             }
             app.sources_gains[idx] = slider_value;
 
@@ -0,0 +1,230 @@
+use crate::{
+    app::ClicksMonitorApp, timing, widget::metronome::beat_flash, window::cue::rehearsal_marks,
+};
+use egui::{Align2, Color32, FontId, Key, KeyboardShortcut, Modifiers, Rect, RichText, vec2};
+
+pub const STAGE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::F11);
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum StageItem {
+    BarBeat,
+    Metronome,
+    CueName,
+    NextMark,
+    NextEvent,
+}
+
+impl StageItem {
+    fn name(&self) -> &str {
+        match self {
+            Self::BarBeat => "Bar.beat",
+            Self::Metronome => "Metronome flash",
+            Self::CueName => "Cue name",
+            Self::NextMark => "Next rehearsal mark",
+            Self::NextEvent => "Countdown to next event",
+        }
+    }
+
+    /// Share of the screen height the row gets, relative to the other rows.
+    fn weight(&self) -> f32 {
+        match self {
+            Self::BarBeat => 3.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Rows of the stage display from top to bottom, each of which can be hidden.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct StageWindowMemory {
+    pub rows: Vec<(StageItem, bool)>,
+    pub flash_downbeat: bool,
+}
+
+impl Default for StageWindowMemory {
+    fn default() -> Self {
+        Self {
+            rows: vec![
+                (StageItem::CueName, true),
+                (StageItem::BarBeat, true),
+                (StageItem::Metronome, true),
+                (StageItem::NextMark, true),
+                (StageItem::NextEvent, true),
+            ],
+            flash_downbeat: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StageState {
+    pub active: bool,
+}
+
+pub fn toggle(app: &mut ClicksMonitorApp, ctx: &egui::Context) {
+    app.stage.active = !app.stage.active;
+    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(app.stage.active));
+}
+
+/// Toggle the stage display with its shortcut, and leave it with escape. Works with interaction
+/// locked, as the display only shows state.
+pub fn handle_shortcuts(app: &mut ClicksMonitorApp, ctx: &egui::Context) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+    if ctx.input_mut(|i| i.consume_shortcut(&STAGE_SHORTCUT))
+        || (app.stage.active && ctx.input(|i| i.key_pressed(Key::Escape)))
+    {
+        toggle(app, ctx);
+    }
+}
+
+/// The next rehearsal mark after the current beat.
+fn next_mark(app: &ClicksMonitorApp) -> Option<String> {
+    let beat_idx = app.status.beat_state().beat_idx;
+    rehearsal_marks(&app.status.cue.cue)
+        .into_iter()
+        .find(|(beat, _)| *beat > beat_idx)
+        .map(|(_, label)| label)
+}
+
+/// The next event after the current beat, and the time until it at the current playrate.
+fn next_event(app: &ClicksMonitorApp) -> Option<(String, u64)> {
+    let cue = &app.status.cue.cue;
+    let beat = app.status.beat_state();
+    let event = cue
+        .events
+        .iter()
+        .filter(|e| e.location > beat.beat_idx && e.event.is_some())
+        .min_by_key(|e| e.location)?;
+    let until = beat.us_to_next_beat as u64 + timing::beat_offset(cue, event.location as usize)
+        - timing::beat_offset(cue, beat.beat_idx as usize + 1);
+    Some((
+        event.event?.get_name().to_string(),
+        until * 100 / app.status.transport.playrate_percent.max(1) as u64,
+    ))
+}
+
+/// Paint a line of text as large as fits in the rect.
+fn fit_text(ui: &egui::Ui, rect: Rect, text: &str, color: Color32) {
+    let chars = text.chars().count().max(1) as f32;
+    // Monospace glyphs are roughly 0.6 times as wide as the font size
+    let size = (rect.height() * 0.8).min(rect.width() * 0.95 / (chars * 0.6));
+    ui.painter().text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        text,
+        FontId::monospace(size),
+        color,
+    );
+}
+
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let rect = ui.max_rect();
+    let background = match app.metronome.flash() {
+        Some((strength, true)) if app.local_memory.stage.flash_downbeat => app
+            .theme
+            .base
+            .lerp_to_gamma(app.theme.active_prim, strength),
+        _ => app.theme.base,
+    };
+    ui.painter().rect_filled(rect, 0.0, background);
+
+    let rows: Vec<StageItem> = app
+        .local_memory
+        .stage
+        .rows
+        .iter()
+        .filter(|(_, shown)| *shown)
+        .map(|(item, _)| *item)
+        .collect();
+    let total_weight: f32 = rows.iter().map(|item| item.weight()).sum();
+    let mut top = rect.top();
+    for item in rows {
+        let height = rect.height() * item.weight() / total_weight;
+        let row = Rect::from_min_size(egui::pos2(rect.left(), top), vec2(rect.width(), height));
+        top += height;
+        match item {
+            StageItem::BarBeat => {
+                let beat = app.status.beat_state().beat;
+                let text = match app.rehearsal.count_in {
+                    Some(count_in) => format!("-{}", count_in.beats_left()),
+                    None => format!("{}.{}", beat.bar_number, beat.count),
+                };
+                let color = if app.status.transport.running {
+                    app.theme.active_prim
+                } else {
+                    app.theme.cued_prim
+                };
+                fit_text(ui, row, &text, color);
+            }
+            StageItem::Metronome => {
+                let size = egui::Vec2::splat(row.height() * 0.8);
+                ui.put(
+                    Rect::from_center_size(row.center(), size),
+                    |ui: &mut egui::Ui| beat_flash(ui, &mut app.metronome, &app.theme, size),
+                );
+            }
+            StageItem::CueName => {
+                let metadata = &app.status.cue.cue.metadata;
+                let text = format!("{} {}", metadata.human_ident.str(), metadata.name.str());
+                fit_text(ui, row, text.trim(), app.theme.neutral_prim);
+            }
+            StageItem::NextMark => {
+                let text = match next_mark(app) {
+                    Some(label) => format!("Next: {}", label),
+                    None => "".to_string(),
+                };
+                fit_text(ui, row, &text, app.theme.warn_prim);
+            }
+            StageItem::NextEvent => {
+                let text = match next_event(app) {
+                    Some((name, us)) => format!("{} in {}", name, timing::format_us(us)),
+                    None => "".to_string(),
+                };
+                fit_text(ui, row, &text, app.theme.neutral_prim);
+            }
+        }
+    }
+}
+
+/// Stage display layout settings, shown in the Time tab.
+pub fn settings(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Stage display").heading());
+        if ui
+            .button(format!(
+                "Open ({})",
+                ui.ctx().format_shortcut(&STAGE_SHORTCUT)
+            ))
+            .clicked()
+        {
+            toggle(app, ui.ctx());
+        }
+    });
+    ui.checkbox(
+        &mut app.local_memory.stage.flash_downbeat,
+        "Flash on downbeat",
+    );
+    let rows = &mut app.local_memory.stage.rows;
+    let mut swap = None;
+    for i in 0..rows.len() {
+        ui.horizontal(|ui| {
+            if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                swap = Some(i - 1);
+            }
+            if ui
+                .add_enabled(i + 1 < rows.len(), egui::Button::new("⏷"))
+                .clicked()
+            {
+                swap = Some(i);
+            }
+            let (item, shown) = &mut rows[i];
+            ui.checkbox(shown, item.name());
+        });
+    }
+    if let Some(i) = swap {
+        rows.swap(i, i + 1);
+    }
+}
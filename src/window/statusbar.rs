@@ -3,9 +3,15 @@ use common::{
     mem::time::format_hms,
     protocol::request::{ControlAction, Request},
 };
-use egui::{Color32, RichText, Widget};
+use egui::{Color32, RichText, Vec2, Widget};
 
-use crate::{app::ClicksMonitorApp, theme, window::WindowTab};
+use crate::{
+    app::ClicksMonitorApp,
+    theme, timing,
+    watchdog::{self, Metric},
+    widget::metronome::beat_flash,
+    window::WindowTab,
+};
 
 pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     egui::menu::bar(ui, |ui| {
@@ -83,11 +89,10 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
         ui.add_space(16.0);
         // Network status
         let color: Color32;
-        if app.rx.len() > 16 {
-            color = app.theme.err_prim;
-            for _ in 0..app.rx.len() {
-                let _ = app.rx.try_recv();
-            }
+        let network_alarm = watchdog::metric_alarm(app, Metric::QueueDepth)
+            .max(watchdog::metric_alarm(app, Metric::HeartbeatAge));
+        if let Some(severity) = network_alarm {
+            color = severity.color(&app.theme);
         } else if !app.udp_client.active {
             color = app.theme.warn_prim;
         } else {
@@ -95,8 +100,12 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
         }
         ui.menu_button(RichText::new("Host Network").color(color), |ui| {
             crate::window::connection::settings(app, ui);
-            if app.rx.len() > 16 {
-                ui.colored_label(app.theme.err_prim, "Living in the past. Clearing cue...");
+            if network_alarm.is_some() {
+                for metric in [Metric::QueueDepth, Metric::HeartbeatAge] {
+                    if let Some(severity) = watchdog::metric_alarm(app, metric) {
+                        ui.colored_label(severity.color(&app.theme), metric.name());
+                    }
+                }
             } else if !app.udp_client.active {
                 ui.colored_label(app.theme.warn_prim, "Not Connected");
             } else {
@@ -125,6 +134,11 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             .cue
             .get_beat(app.status.beat_state().beat_idx)
             .unwrap_or_default();
+        let jitter = app.metronome.jitter();
+        beat_flash(ui, &mut app.metronome, &app.theme, Vec2::splat(12.0)).on_hover_text(format!(
+            "Beat data jitter {} ms. Network latency is not compensated.",
+            jitter.as_millis()
+        ));
         ui.menu_button(
             RichText::new(format!(
                 "LTC: {}   BEAT: {}.{}",
@@ -163,16 +177,29 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             },
         );
 
+        // Playback countdown
+        if let Some((channel, seconds)) = crate::window::playback::remaining_times(app).first() {
+            ui.menu_button(
+                RichText::new(format!(
+                    "END: {:02} {}",
+                    channel + 1,
+                    crate::window::playback::format_duration(*seconds)
+                ))
+                .monospace()
+                .color(crate::window::playback::countdown_color(app, *seconds)),
+                |ui| {
+                    playback_menu(app, ui);
+                },
+            );
+        }
+
         // Clock
         let system_time = chrono::prelude::Utc::now().timestamp_micros() as u64;
         let host_time = app.last_heartbeat.system_time;
         let diff = system_time.abs_diff(host_time * 1000000);
         let color = if host_time > 0 {
-            if diff < 5 * 1000000 {
-                app.theme.active_prim
-            } else {
-                app.theme.err_prim
-            }
+            watchdog::metric_alarm(app, Metric::ClockSkew)
+                .map_or(app.theme.active_prim, |severity| severity.color(&app.theme))
         } else {
             app.theme.warn_prim
         };
@@ -197,13 +224,9 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
 
         // Performance
         ui.colored_label(
-            if app.last_heartbeat.cpu_use_audio > 80.0
-                || app.last_heartbeat.process_freq_main < 10000
-            {
-                app.theme.err_prim
-            } else {
-                app.theme.active_prim
-            },
+            watchdog::metric_alarm(app, Metric::CpuUseAudio)
+                .max(watchdog::metric_alarm(app, Metric::ProcessFreqMain))
+                .map_or(app.theme.active_prim, |severity| severity.color(&app.theme)),
             egui::RichText::new(format!(
                 "PERF: {:2.1}%  {}kHz",
                 app.last_heartbeat.cpu_use_audio,
@@ -212,6 +235,55 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             .monospace(),
         );
 
+        // Beat timing alert
+        if let Some(error) = app.beat_accuracy.active_alert()
+            && ui
+                .add(
+                    egui::Button::new(
+                        egui::RichText::new(format!("BEAT {:+.1}ms", error))
+                            .monospace()
+                            .color(app.theme.err_prim),
+                    )
+                    .frame(false),
+                )
+                .on_hover_text("A beat arrived off the grid by more than the alert threshold")
+                .clicked()
+        {
+            app.local_memory.current_tab = WindowTab::SystemPerformance;
+        }
+
+        // Alarms, blinking until acknowledged
+        if let Some((worst, active)) = watchdog::summary(app) {
+            let blink = ui.input(|i| i.time * 2.0) as i64 % 2 == 0;
+            let color = match worst {
+                Some(severity) if blink => severity.color(&app.theme),
+                Some(_) => app.theme.base_wk,
+                None => app.theme.neutral_prim,
+            };
+            ui.menu_button(
+                RichText::new(format!(
+                    "ALARM{}: {}",
+                    if active == 1 { "" } else { "S" },
+                    active
+                ))
+                .monospace()
+                .color(color),
+                |ui| {
+                    crate::window::alarms::active_list(app, ui);
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Acknowledge all").clicked() {
+                            watchdog::acknowledge_all(app);
+                        }
+                        if ui.button("Show alarms").clicked() {
+                            app.local_memory.current_tab = WindowTab::SystemAlarms;
+                            ui.close_menu();
+                        }
+                    });
+                },
+            );
+        }
+
         // Interaction lock
         if !app.local_memory.security.allow_interaction {
             ui.colored_label(
@@ -224,10 +296,51 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             );
         }
 
+        // Rehearsal loop
+        if let Some(label) = crate::rehearsal::loop_label(app) {
+            ui.menu_button(
+                RichText::new(label).monospace().color(app.theme.warn_prim),
+                |ui| {
+                    crate::rehearsal::loop_controls(app, ui);
+                },
+            );
+        }
+
+        // Staged mixer changes
+        let staged = app.local_memory.history.staged_count();
+        if app.local_memory.history.armed || staged > 0 {
+            ui.menu_button(
+                RichText::new(format!("ARMED: {} staged", staged))
+                    .monospace()
+                    .color(if staged > 0 {
+                        app.theme.warn_prim
+                    } else {
+                        app.theme.active_prim
+                    }),
+                |ui| {
+                    crate::history::controls(app, ui);
+                },
+            );
+        }
+
         crate::window::transport::control_field(app, ui);
     });
 }
 
+fn playback_menu(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    egui::Grid::new("playback-menu-grid").show(ui, |ui| {
+        for (channel, seconds) in crate::window::playback::remaining_times(app) {
+            ui.label(format!("{:02}", channel + 1));
+            ui.label(app.system_config.channels[channel as usize + 2].name.str());
+            ui.colored_label(
+                crate::window::playback::countdown_color(app, seconds),
+                crate::window::playback::format_duration(seconds),
+            );
+            ui.end_row();
+        }
+    });
+}
+
 fn transport_menu(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     if !app.local_memory.security.allow_interaction {
         ui.disable();
@@ -261,6 +374,31 @@ pub fn cues_menu(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             ui.label(format!("{:0>3}", i));
             ui.colored_label(color, cue.metadata.human_ident.str());
             ui.colored_label(color, cue.metadata.name.str());
+            if i == app.status.cue.cue_idx as usize {
+                let timing = timing::cue_timing(
+                    &app.status.cue.cue,
+                    &app.status.beat_state(),
+                    app.status.transport.playrate_percent,
+                    app.status.transport.vlt,
+                );
+                ui.colored_label(
+                    color,
+                    format!(
+                        "-{}{} / {}",
+                        if timing.open_ended { "≥" } else { "" },
+                        timing::format_us(timing.remaining_us),
+                        timing::format_us(timing.total_us)
+                    ),
+                );
+            } else {
+                ui.colored_label(
+                    color,
+                    timing::format_us(timing::cue_length(
+                        cue,
+                        app.status.transport.playrate_percent,
+                    )),
+                );
+            }
             if ui.add_enabled(app.local_memory.security.allow_interaction, egui::Button::new("GOTO").small()).on_disabled_hover_text("Transport controls are disabled when client is locked. Unlock client to change cues.").clicked() {
                 app.udp_client.send_msg(Request::ControlAction(
                     ControlAction::LoadCueByIndex(i as u8),
@@ -0,0 +1,218 @@
+use crate::{app::ClicksMonitorApp, timing, widget::plot::tick_step};
+use common::event::EventDescription;
+use egui::{Align2, FontId, Pos2, Rect, RichText, Sense, Stroke, pos2, vec2};
+
+const AXIS_MARGIN: f32 = 48.0;
+
+fn bpm(length_us: u32) -> f32 {
+    60_000_000.0 / length_us.max(1) as f32
+}
+
+pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let cue = app.status.cue.cue.clone();
+    let playrate = app.status.transport.playrate_percent as f32 / 100.0;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("Tempo map: {}", cue.metadata.name.str())).heading());
+        if app.status.transport.playrate_percent != 100 {
+            ui.colored_label(
+                app.theme.cued_prim,
+                format!("Playrate {}%", app.status.transport.playrate_percent),
+            );
+        }
+    });
+    if cue.beats.is_empty() {
+        ui.label("No cue loaded.");
+        return;
+    }
+
+    // Beat start times in seconds at normal playrate, with the cue end as the last point
+    let mut times = Vec::with_capacity(cue.beats.len() + 1);
+    let mut t = 0.0;
+    for beat in &cue.beats {
+        times.push(t);
+        t += beat.length as f32 / 1_000_000.0;
+    }
+    times.push(t);
+    let duration = t.max(f32::EPSILON);
+
+    let tempos: Vec<f32> = cue.beats.iter().map(|b| bpm(b.length)).collect();
+    let min_tempo = tempos
+        .iter()
+        .map(|t| t.min(t * playrate))
+        .fold(f32::MAX, f32::min);
+    let max_tempo = tempos
+        .iter()
+        .map(|t| t.max(t * playrate))
+        .fold(0.0, f32::max);
+    let padding = ((max_tempo - min_tempo) * 0.1).max(5.0);
+    let (min_tempo, max_tempo) = (min_tempo - padding, max_tempo + padding);
+
+    let (resp, p) = ui.allocate_painter(ui.available_size(), Sense::hover());
+    let plot = Rect::from_min_max(
+        resp.rect.min + vec2(AXIS_MARGIN, 8.0),
+        resp.rect.max - vec2(8.0, AXIS_MARGIN),
+    );
+    let to_screen = |time: f32, tempo: f32| {
+        pos2(
+            plot.left() + time / duration * plot.width(),
+            plot.bottom() - (tempo - min_tempo) / (max_tempo - min_tempo) * plot.height(),
+        )
+    };
+
+    p.rect_filled(plot, 2.0, app.theme.base_ex);
+    let grid_stroke = Stroke::new(1.0, app.theme.base_wk);
+    let text_font = FontId::proportional(12.0);
+
+    // Axes
+    let tempo_step = tick_step(max_tempo - min_tempo, 6.0);
+    let mut tempo_tick = (min_tempo / tempo_step).ceil() * tempo_step;
+    while tempo_tick <= max_tempo {
+        let y = to_screen(0.0, tempo_tick).y;
+        p.hline(plot.x_range(), y, grid_stroke);
+        p.text(
+            pos2(plot.left() - 4.0, y),
+            Align2::RIGHT_CENTER,
+            format!("{:.0}", tempo_tick),
+            text_font.clone(),
+            app.theme.neutral_prim,
+        );
+        tempo_tick += tempo_step;
+    }
+    let time_step = tick_step(duration, 10.0).max(1.0);
+    let mut time_tick = 0.0;
+    while time_tick <= duration {
+        let x = to_screen(time_tick, min_tempo).x;
+        p.vline(x, plot.y_range(), grid_stroke);
+        p.text(
+            pos2(x, plot.bottom() + 4.0),
+            Align2::CENTER_TOP,
+            timing::format_us((time_tick * 1_000_000.0) as u64),
+            text_font.clone(),
+            app.theme.neutral_prim,
+        );
+        time_tick += time_step;
+    }
+    p.text(
+        pos2(plot.left() - 4.0, plot.top()),
+        Align2::RIGHT_BOTTOM,
+        "BPM",
+        text_font.clone(),
+        app.theme.neutral_prim,
+    );
+
+    // Tempo change annotations
+    for event in cue.events.iter() {
+        let location = event.location as usize;
+        if location >= cue.beats.len() {
+            continue;
+        }
+        let x = to_screen(times[location], min_tempo).x;
+        match event.event {
+            Some(EventDescription::TempoChangeEvent { tempo }) => {
+                p.vline(x, plot.y_range(), Stroke::new(1.0, app.theme.warn_prim));
+                p.text(
+                    pos2(x + 2.0, plot.top() + 2.0),
+                    Align2::LEFT_TOP,
+                    format!("♩={}", tempo),
+                    text_font.clone(),
+                    app.theme.warn_prim,
+                );
+            }
+            Some(EventDescription::GradualTempoChangeEvent {
+                start_tempo,
+                end_tempo,
+                length,
+            }) => {
+                let end = (location + length as usize).min(cue.beats.len());
+                let end_x = to_screen(times[end], min_tempo).x;
+                p.rect_filled(
+                    Rect::from_x_y_ranges(x..=end_x, plot.y_range()),
+                    0.0,
+                    app.theme.warn_prim.gamma_multiply(0.1),
+                );
+                p.text(
+                    pos2(x + 2.0, plot.top() + 16.0),
+                    Align2::LEFT_TOP,
+                    format!(
+                        "{} {}→{}",
+                        if end_tempo > start_tempo {
+                            "accel."
+                        } else {
+                            "rit."
+                        },
+                        start_tempo,
+                        end_tempo
+                    ),
+                    text_font.clone(),
+                    app.theme.warn_prim,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    // Tempo as steps, one per beat, with the playrate adjusted tempo on top
+    let steps = |scale: f32| -> Vec<Pos2> {
+        tempos
+            .iter()
+            .enumerate()
+            .flat_map(|(i, tempo)| {
+                [
+                    to_screen(times[i], tempo * scale),
+                    to_screen(times[i + 1], tempo * scale),
+                ]
+            })
+            .collect()
+    };
+    p.line(steps(1.0), Stroke::new(2.0, app.theme.neutral_prim));
+    if app.status.transport.playrate_percent != 100 {
+        p.line(steps(playrate), Stroke::new(1.5, app.theme.cued_prim));
+    }
+
+    // Playhead
+    let beat = app.status.beat_state();
+    if (beat.beat_idx as usize) < cue.beats.len() {
+        let current = cue.beats[beat.beat_idx as usize].length;
+        let into_beat = current.saturating_sub(beat.us_to_next_beat) as f32 / 1_000_000.0;
+        let x = to_screen(times[beat.beat_idx as usize] + into_beat, min_tempo).x;
+        p.vline(
+            x,
+            plot.y_range(),
+            Stroke::new(
+                2.0,
+                if app.status.transport.running {
+                    app.theme.active_prim
+                } else {
+                    app.theme.cued_prim
+                },
+            ),
+        );
+    }
+
+    // Hover readout
+    if let Some(pos) = resp.hover_pos()
+        && plot.contains(pos)
+    {
+        let time = (pos.x - plot.left()) / plot.width() * duration;
+        let idx = times.partition_point(|t| *t <= time).saturating_sub(1);
+        if let Some(beat) = cue.beats.get(idx) {
+            p.vline(
+                pos.x,
+                plot.y_range(),
+                Stroke::new(1.0, app.theme.neutral_prim),
+            );
+            resp.on_hover_text(format!(
+                "{}.{} (beat {})\n{:.1} BPM{}",
+                beat.bar_number,
+                beat.count,
+                idx,
+                tempos[idx],
+                if app.status.transport.playrate_percent != 100 {
+                    format!(", {:.1} at playrate", tempos[idx] * playrate)
+                } else {
+                    "".to_string()
+                }
+            ));
+        }
+    }
+}
@@ -12,7 +12,118 @@ use egui::{
 const GRID_MARGIN: f32 = 5.0;
 const BORDER_MARGIN: f32 = 16.0;
 
-const ASPECT_RATIO: f32 = 180.0 / 800.0;
+/// Session stopwatch and lap timers. Running timers store the wall clock time they were started
+/// at, so they keep counting while the monitor is closed.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TimeWindowMemory {
+    pub session: SessionTimer,
+    pub laps: Vec<SessionTimer>,
+    pub active_lap: Option<usize>,
+}
+
+impl Default for TimeWindowMemory {
+    fn default() -> Self {
+        Self {
+            session: SessionTimer::new("Session"),
+            laps: ["Act 1", "Interval", "Act 2"]
+                .iter()
+                .map(|name| SessionTimer::new(name))
+                .collect(),
+            active_lap: None,
+        }
+    }
+}
+
+impl TimeWindowMemory {
+    /// Stop the running lap and start the next one, starting the session if needed.
+    pub fn next_lap(&mut self) {
+        let next = self.active_lap.map_or(0, |lap| lap + 1);
+        if next >= self.laps.len() {
+            return;
+        }
+        if let Some(lap) = self.active_lap.and_then(|lap| self.laps.get_mut(lap)) {
+            lap.stop();
+        }
+        self.session.start();
+        self.laps[next].start();
+        self.active_lap = Some(next);
+    }
+
+    /// Remove a lap, keeping the active lap pointing at the same lap.
+    pub fn remove_lap(&mut self, idx: usize) {
+        if idx >= self.laps.len() {
+            return;
+        }
+        self.laps.remove(idx);
+        self.active_lap = match self.active_lap {
+            Some(active) if active == idx => None,
+            Some(active) if active > idx => Some(active - 1),
+            active => active,
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.session.stop();
+        for lap in &mut self.laps {
+            lap.stop();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.session.reset();
+        for lap in &mut self.laps {
+            lap.reset();
+        }
+        self.active_lap = None;
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+pub struct SessionTimer {
+    pub name: String,
+    /// Unix time in ms the timer was last started at, if running.
+    started: Option<i64>,
+    /// Time accumulated before the last start, in ms.
+    accumulated: i64,
+}
+
+impl SessionTimer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(chrono::Utc::now().timestamp_millis());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.accumulated = self.elapsed();
+        self.started = None;
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulated = 0;
+        self.started = None;
+    }
+
+    /// Elapsed running time in ms.
+    pub fn elapsed(&self) -> i64 {
+        self.accumulated
+            + self
+                .started
+                .map_or(0, |start| chrono::Utc::now().timestamp_millis() - start)
+    }
+}
 
 pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     let size = 800.0 * (ui.available_width() - 4.0 * BORDER_MARGIN - 3.0 * GRID_MARGIN) / 1600.0;
@@ -37,13 +148,7 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             draw_smpte_time(app, ui, smpte_time, "SMPTE Timecode".to_string(), size);
 
             // Session timer
-            draw_session_timer(
-                app,
-                ui,
-                [systime, host_time, systime, host_time],
-                "Session timer".to_string(),
-                size,
-            );
+            draw_session_timer(app, ui, "Session timer".to_string(), size);
 
             ui.end_row();
 
@@ -77,10 +182,15 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
             ui.end_row();
 
             // Monitor uptime
-            draw_uptime(app, ui, 0, "Monitor uptime".to_string(), size);
+            let monitor_uptime = app.started.elapsed().as_secs();
+            draw_uptime(app, ui, monitor_uptime, "Monitor uptime".to_string(), size);
             // Core uptime
-            draw_uptime(app, ui, 0, "Core uptime".to_string(), size);
+            let core_uptime = app.last_heartbeat.uptime;
+            draw_uptime(app, ui, core_uptime, "Core uptime".to_string(), size);
         });
+
+    ui.separator();
+    crate::window::stage::settings(app, ui);
 }
 
 pub fn draw_wall_time(
@@ -108,48 +218,102 @@ pub fn draw_wall_time(
     );
 }
 
-pub fn draw_session_timer(
-    app: &mut ClicksMonitorApp,
-    ui: &mut egui::Ui,
-    time: [NaiveTime; 4],
-    title: String,
-    size: f32,
-) {
+pub fn draw_session_timer(app: &mut ClicksMonitorApp, ui: &mut egui::Ui, title: String, size: f32) {
+    let session = &app.local_memory.time.session;
     draw_big_clock_in_frame(
         app,
         ui,
-        format!(
-            "{:02}{:02}{:02}  ",
-            time[0].hour(),
-            time[0].minute(),
-            time[0].second()
-        )
-        .as_str(),
+        format_stopwatch(session.elapsed()).as_str(),
         [b':', b':', b' '],
-        app.theme.neutral_prim,
+        if session.running() {
+            app.theme.active_prim
+        } else {
+            app.theme.neutral_prim
+        },
         size,
         title,
         move |app_n, ui_n| {
-            for i in 0..3 {
+            session_controls(app_n, ui_n);
+            for i in 0..app_n.local_memory.time.laps.len() {
+                let lap = &app_n.local_memory.time.laps[i];
+                ui_n.label(lap.name.as_str());
                 draw_big_clock(
                     ui_n,
-                    format!(
-                        "{:02}{:02}{:02}  ",
-                        time[i + 1].hour(),
-                        time[i + 1].minute(),
-                        time[i + 1].second()
-                    )
-                    .as_str(),
+                    format_stopwatch(lap.elapsed()).as_str(),
                     [b':', b':', b' '],
                     app_n.theme.base_ex,
-                    app_n.theme.neutral_prim,
-                    size * ui_n.available_height() / (size / ASPECT_RATIO),
+                    if lap.running() {
+                        app_n.theme.active_prim
+                    } else {
+                        app_n.theme.neutral_prim
+                    },
+                    size * 0.5,
                 );
             }
         },
     );
 }
 
+fn session_controls(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
+    let timers = &mut app.local_memory.time;
+    ui.horizontal(|ui| {
+        if timers.session.running() {
+            if ui.button("Stop").clicked() {
+                timers.stop();
+            }
+        } else if ui.button("Start").clicked() {
+            timers.session.start();
+        }
+        let next = timers.active_lap.map_or(0, |lap| lap + 1);
+        if ui
+            .add_enabled(
+                next < timers.laps.len(),
+                egui::Button::new(match timers.laps.get(next) {
+                    Some(lap) => format!("Start {}", lap.name),
+                    None => "Start next lap".to_string(),
+                }),
+            )
+            .clicked()
+        {
+            timers.next_lap();
+        }
+        if ui.button("Reset").clicked() {
+            timers.reset();
+        }
+        ui.menu_button("Laps", |ui| {
+            let mut remove = None;
+            for (i, lap) in timers.laps.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    egui::TextEdit::singleline(&mut lap.name)
+                        .desired_width(120.0)
+                        .ui(ui);
+                    if ui.small_button("✕").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                timers.remove_lap(i);
+            }
+            if ui.button("Add lap").clicked() {
+                timers
+                    .laps
+                    .push(SessionTimer::new(&format!("Lap {}", timers.laps.len() + 1)));
+            }
+        });
+    });
+}
+
+fn format_stopwatch(elapsed_ms: i64) -> String {
+    let seconds = elapsed_ms / 1000;
+    format!(
+        "{:02}{:02}{:02}  ",
+        seconds / 3600 % 100,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
 pub fn draw_smpte_time(
     app: &mut ClicksMonitorApp,
     ui: &mut egui::Ui,
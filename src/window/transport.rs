@@ -1,4 +1,4 @@
-use crate::app::ClicksMonitorApp;
+use crate::{app::ClicksMonitorApp, rehearsal, timing};
 use common::{
     event::JumpModeChange,
     protocol::request::{ControlAction, Request},
@@ -43,14 +43,22 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                         RichText::new(app.status.cue.cue.metadata.human_ident.str()).heading(),
                     );
                     ui.label(RichText::new(app.status.cue.cue.metadata.name.str()).size(64.0));
-                    ui.label(
-                        RichText::new(format!(
-                            "{}.{}",
-                            app.status.beat_state().beat.bar_number,
-                            app.status.beat_state().beat.count
-                        ))
-                        .size(64.0),
-                    );
+                    if let Some(count_in) = app.rehearsal.count_in {
+                        ui.label(
+                            RichText::new(format!("Count-in {}", count_in.beats_left()))
+                                .size(64.0)
+                                .color(app.theme.cued_prim),
+                        );
+                    } else {
+                        ui.label(
+                            RichText::new(format!(
+                                "{}.{}",
+                                app.status.beat_state().beat.bar_number,
+                                app.status.beat_state().beat.count
+                            ))
+                            .size(64.0),
+                        );
+                    }
                     ProgressBar::new(
                         app.status.beat_state().beat_idx as f32
                             / app.status.cue.cue.beats.len() as f32,
@@ -61,6 +69,7 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                         app.theme.cued_prim
                     })
                     .ui(ui);
+                    cue_time(app, ui);
                 });
             });
 
@@ -93,9 +102,14 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                 }
             });
 
-            if big_button(ui, "Play", app.theme.cued_prim, size).clicked() {
-                app.udp_client
-                    .send_msg(Request::ControlAction(ControlAction::TransportStart));
+            let play_text = match app.local_memory.count_in_bars {
+                bars if bars > 0 && rehearsal::count_in_available(app) => {
+                    format!("Play ({} bar count-in)", bars)
+                }
+                _ => "Play".to_string(),
+            };
+            if big_button(ui, &play_text, app.theme.cued_prim, size).clicked() {
+                start(app, app.status.beat_state().beat_idx);
             }
 
             ui.end_row();
@@ -113,12 +127,82 @@ pub fn display(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
                     )));
             }
 
+            ui.horizontal(|ui| {
+                let available = rehearsal::count_in_available(app);
+                ui.add_enabled_ui(available, |ui| {
+                    ui.label("Count-in");
+                    for (value, text) in [(0, "None"), (1, "1 bar"), (2, "2 bars")] {
+                        ui.selectable_value(&mut app.local_memory.count_in_bars, value, text);
+                    }
+                });
+                if app.rehearsal.count_in.is_some() && ui.button("Cancel count-in").clicked() {
+                    rehearsal::cancel_count_in(app);
+                }
+                if !available {
+                    ui.colored_label(app.theme.warn_prim, rehearsal::MSG_COUNT_IN_UNAVAILABLE);
+                }
+            });
+
             ui.end_row();
         });
         control_field(app, ui);
     });
 }
 
+/// Start the transport from a beat, with the selected count-in if there is one.
+pub fn start(app: &mut ClicksMonitorApp, beat_idx: u16) {
+    if app.local_memory.count_in_bars > 0
+        && rehearsal::count_in_available(app)
+        && !app.status.transport.running
+    {
+        rehearsal::start_count_in(app, beat_idx, app.local_memory.count_in_bars);
+        return;
+    }
+    if beat_idx != app.status.beat_state().beat_idx {
+        app.udp_client
+            .send_msg(Request::ControlAction(ControlAction::TransportJumpBeat(
+                beat_idx,
+            )));
+    }
+    app.udp_client
+        .send_msg(Request::ControlAction(ControlAction::TransportStart));
+}
+
+fn cue_time(app: &ClicksMonitorApp, ui: &mut egui::Ui) {
+    let timing = timing::cue_timing(
+        &app.status.cue.cue,
+        &app.status.beat_state(),
+        app.status.transport.playrate_percent,
+        app.status.transport.vlt,
+    );
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(format!("Elapsed {}", timing::format_us(timing.elapsed_us))).size(24.0),
+        );
+        ui.separator();
+        ui.label(
+            RichText::new(format!(
+                "Remaining {}{}",
+                if timing.open_ended { "≥ " } else { "" },
+                timing::format_us(timing.remaining_us)
+            ))
+            .size(24.0)
+            .color(app.theme.active_prim),
+        )
+        .on_hover_text(if timing.open_ended {
+            "A vamp lies ahead, the cue runs at least this long"
+        } else {
+            "Following the jumps that will be taken in the current jump mode"
+        });
+        ui.separator();
+        ui.label(RichText::new(format!("Total {}", timing::format_us(timing.total_us))).size(24.0));
+        if timing.holds > 0 {
+            ui.separator();
+            ui.colored_label(app.theme.warn_prim, format!("+ {} hold(s)", timing.holds));
+        }
+    });
+}
+
 pub fn control_field(app: &mut ClicksMonitorApp, ui: &mut egui::Ui) {
     let mut keyboard_input = String::new();
     ui.add(egui::TextEdit::singleline(&mut keyboard_input));